@@ -1,170 +1,491 @@
-use std::f64::INFINITY;
-use std::fs::File;
-use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
 use fastrand::f64;
 
-use crate::objects::{HitRecord, Objects};
-use crate::ray::Ray;
-use crate::utils::{deg_to_rad, Interval};
+use crate::error::{Error, Result};
+use crate::image::{Aovs, Framebuffer};
+use crate::integrators::Integrator;
+use crate::objects::HitRecord;
+use crate::scene::Scene;
+use crate::ray::{Differentials, Ray};
+use crate::utils::deg_to_rad;
 use crate::vec3::{Color3, Point3, Vec3};
 
 
 
-pub struct Camera {
+/// The settings of a camera, turned into a [`Camera`] by [`CameraConfig::build`].
+///
+/// ```
+/// use rustracer::CameraConfig;
+/// use rustracer::vec3::Point3;
+///
+/// let cam = CameraConfig {
+///     image_width: 320,
+///     lookfrom: Point3::new(0., 2., 5.),
+///     vfov: 40.,
+///     ..Default::default()
+/// }.build()?;
+/// assert_eq!(cam.image_height(), 180);
+/// # Ok::<(), rustracer::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraConfig {
     pub aspect: f64,
     pub image_width: u32,
     pub samples_per_pixel: u32,
-    pub max_depth: u32,
     pub lookfrom: Point3,
     pub lookat: Point3,
     pub vup: Vec3,
     pub vfov: f64,
     pub defocus_angle: f64,
     pub focus_dist: f64,
-    pub output: File,
-    
-    image_height: u32,
-    center: Point3,
-    pixel00: Point3,
-    delta_u: Vec3,
-    delta_v: Vec3,
-    u: Vec3,
-    v: Vec3,
-    w: Vec3,
-    defocus_disk_u: Vec3,
-    defocus_disk_v: Vec3,
+    /// Number of worker threads. 0 uses all available cores.
+    pub threads: usize,
+    /// Side length in pixels of the square tiles handed to the workers.
+    pub tile_size: u32,
+    /// Base seed of the per-tile random generators, so that renders are reproducible.
+    pub seed: u64,
 }
 
-impl Default for Camera {
+impl Default for CameraConfig {
     fn default() -> Self {
         Self {
             aspect: 16.0/9.0,
             image_width: 720,
             samples_per_pixel: 10,
-            max_depth: 10,
             lookfrom: Point3::new(0.0, 0.0, -1.0),
             lookat: Point3::new(0.0, 0.0, 0.0),
             vup: Vec3::new(0.0, 1.0, 0.0),
             vfov: 90.0,
             defocus_angle: 0.0,
             focus_dist: 10.0,
-            output: File::create("./out.ppm").unwrap(),
-
-            // Dummy initialization
-            image_height: 0,
-            center: Point3::new(0.0, 0.0, 0.0),
-            pixel00: Point3::new(0.0, 0.0, 0.0),
-            delta_u: Vec3::new(0.0, 0.0, 0.0),
-            delta_v: Vec3::new(0.0, 0.0, 0.0),
-            u: Vec3::new(0.0, 0.0, 0.0),
-            v: Vec3::new(0.0, 0.0, 0.0),
-            w: Vec3::new(0.0, 0.0, 0.0),
-            defocus_disk_u: Vec3::new(0.0, 0.0, 0.0),
-            defocus_disk_v: Vec3::new(0.0, 0.0, 0.0),
+            threads: 0,
+            tile_size: 32,
+            seed: 0,
         }
     }
 }
 
-impl Camera {
-    pub fn render(&mut self, mut objects: Objects) {
-        self.initialize();
-
-        let header = format!("P3\n{} {}\n255\n", self.image_width, self.image_height);
-        let _ = self.output.write(header.as_bytes()).unwrap();
-    
-        for row in 0..self.image_height {
-            print!("Row {}: ", row);
-            io::stdout().flush().unwrap();
-    
-            for col in 0..self.image_width {
-                let mut frag_sum_color: Color3 = Color3::new(0., 0., 0.);
-                (0..self.samples_per_pixel as u32).into_iter().for_each(|_i| {
-                    let r = self.get_ray(col, row);
-                    frag_sum_color += self.ray_color(&r, self.max_depth, &mut objects);
-                });
-    
-                self.write_color(frag_sum_color);
-            }
-            
-            println!("Done.");
+impl CameraConfig {
+    /// Checks that the settings describe a usable camera.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |message: &str| Err(Error::invalid_parameter(message));
+        if self.image_width == 0 {
+            return invalid("the image width must be at least 1");
+        }
+        if !(self.aspect > 0. && self.aspect.is_finite()) {
+            return invalid("the aspect ratio must be positive");
+        }
+        if self.samples_per_pixel == 0 {
+            return invalid("at least 1 sample per pixel is needed");
+        }
+        if !(self.vfov > 0. && self.vfov < 180.) {
+            return invalid("the vertical field of view must be between 0 and 180 degrees");
+        }
+        if !(self.focus_dist > 0. && self.focus_dist.is_finite()) {
+            return invalid("the focus distance must be positive");
+        }
+        if self.defocus_angle < 0. {
+            return invalid("the defocus angle can't be negative");
         }
+        let view: Vec3 = self.lookfrom - self.lookat;
+        if view.len_squared() == 0. {
+            return invalid("lookfrom and lookat must be different points");
+        }
+        if (self.vup & view).len_squared() <= 1e-12 * self.vup.len_squared() * view.len_squared() {
+            return invalid("vup must not be zero or parallel to the view direction");
+        }
+        Ok(())
     }
 
-    pub fn initialize(&mut self) {
-        self.image_height = {
+    /// Derives the viewport from the settings, once they are validated.
+    pub fn build(&self) -> Result<Camera> {
+        self.validate()?;
+        let image_height: u32 = {
             let height: f64 = self.image_width as f64 / self.aspect;
             if height < 1. { 1 }
             else { height as u32 }
         };
 
-        self.center = self.lookfrom;
+        let center: Point3 = self.lookfrom;
 
         // Viewport
 
         let theta: f64 = deg_to_rad(self.vfov);
         let h: f64 = (theta/2.0).tan();
         let viewport_height: f64 = 2. * h * self.focus_dist;
-        let viewport_width: f64 = viewport_height * self.image_width as f64 / self.image_height as f64;
+        let viewport_width: f64 = viewport_height * self.image_width as f64 / image_height as f64;
+
+        let w: Vec3 = (self.lookfrom - self.lookat).unit();
+        let u: Vec3 = (self.vup & w).unit();
+        let v: Vec3 = w & u;
 
-        self.w = (self.lookfrom - self.lookat).unit();
-        self.u = (self.vup & self.w).unit();
-        self.v = self.w & self.u;
+        let viewport_u: Vec3 = viewport_width * u;
+        let viewport_v: Vec3 = viewport_height * (-v);
 
-        let viewport_u: Vec3 = viewport_width * self.u;
-        let viewport_v: Vec3 = viewport_height * (-self.v);
+        let delta_u: Vec3 = viewport_u / self.image_width as f64;
+        let delta_v: Vec3 = viewport_v / image_height as f64;
 
-        self.delta_u = viewport_u / self.image_width as f64;
-        self.delta_v = viewport_v / self.image_height as f64;
+        let viewport_upper_left = center - self.focus_dist * w - viewport_u/2. - viewport_v/2.;
+        let pixel00: Point3 = viewport_upper_left + 0.5 * (delta_u + delta_v);
 
-        let viewport_upper_left = self.center - self.focus_dist * self.w - viewport_u/2. - viewport_v/2.;
-        self.pixel00 = viewport_upper_left + 0.5 * (self.delta_u + self.delta_v);
-        
         let defocus_radius: f64 = self.focus_dist * deg_to_rad(self.defocus_angle / 2.0).tan();
-        self.defocus_disk_u = self.u * defocus_radius;
-        self.defocus_disk_v = self.v * defocus_radius;
+        Ok(Camera {
+            config: self.clone(),
+            image_height,
+            center,
+            pixel00,
+            delta_u,
+            delta_v,
+            u,
+            v,
+            w,
+            defocus_disk_u: u * defocus_radius,
+            defocus_disk_v: v * defocus_radius,
+        })
+    }
+}
+
+/// A camera ready to render, built from a [`CameraConfig`].
+/// It never changes once built, so one camera can be shared by several threads.
+#[derive(Debug, Clone)]
+pub struct Camera {
+    config: CameraConfig,
+    image_height: u32,
+    center: Point3,
+    pixel00: Point3,
+    delta_u: Vec3,
+    delta_v: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    defocus_disk_u: Vec3,
+    defocus_disk_v: Vec3,
+}
+
+impl Camera {
+    /// The settings the camera was built from.
+    pub fn config(&self) -> &CameraConfig {
+        &self.config
+    }
+
+    pub fn image_width(&self) -> u32 {
+        self.config.image_width
+    }
+
+    /// Follows from the width and the aspect ratio.
+    pub fn image_height(&self) -> u32 {
+        self.image_height
+    }
+
+    /// Renders `scene` with `integrator` into a new framebuffer.
+    pub fn render(&self, scene: &Scene, integrator: &dyn Integrator) -> Framebuffer {
+        self.render_with_progress(scene, integrator, None, &|_, _| {})
+    }
+
+    /// Same as [`Camera::render`], also filling the output variables of the first hits.
+    pub fn render_with_aovs(&self, scene: &Scene, integrator: &dyn Integrator) -> (Framebuffer, Aovs) {
+        let mut aovs = Aovs::new(self.image_width(), self.image_height);
+        let framebuffer: Framebuffer = self.render_with_progress(scene, integrator, Some(&mut aovs), &|_, _| {});
+        (framebuffer, aovs)
+    }
+
+    /// Renders `scene`, filling `aovs` when given, which must be the size of the image.
+    /// `progress` is called on the calling thread with the number of tiles done and the total, after each tile.
+    pub fn render_with_progress(
+        &self,
+        scene: &Scene,
+        integrator: &dyn Integrator,
+        mut aovs: Option<&mut Aovs>,
+        progress: &dyn Fn(usize, usize),
+    ) -> Framebuffer {
+        let with_aovs: bool = aovs.is_some();
+
+        let tiles: Vec<Tile> = self.tiles();
+        let threads: usize = match self.config.threads {
+            0 => thread::available_parallelism().map_or(1, |n| n.get()),
+            n => n,
+        }.min(tiles.len());
+
+        let mut framebuffer = Framebuffer::new(self.config.image_width, self.image_height);
+        let next_tile = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel::<(usize, Vec<Pixel>)>();
+
+        thread::scope(|s| {
+            for _ in 0..threads {
+                let sender = sender.clone();
+                let (cam, tiles, next_tile) = (self, &tiles, &next_tile);
+                s.spawn(move || loop {
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    let Some(tile) = tiles.get(index) else { break };
+                    if sender.send((index, cam.render_tile(index, tile, scene, integrator, with_aovs))).is_err() { break }
+                });
+            }
+            drop(sender);
+
+            for (done, (index, pixels)) in receiver.iter().enumerate() {
+                let tile = &tiles[index];
+                for (i, pixel) in pixels.into_iter().enumerate() {
+                    let (x, y) = (tile.x + i as u32 % tile.width, tile.y + i as u32 / tile.width);
+                    framebuffer.set(x, y, pixel.color);
+                    if let (Some(aovs), Some(features)) = (&mut aovs, pixel.features) {
+                        features.store(aovs, x, y);
+                    }
+                }
+                progress(done + 1, tiles.len());
+            }
+        });
+
+        framebuffer
+    }
+
+    /// Splits the image into `tile_size`-wide tiles, row by row.
+    fn tiles(&self) -> Vec<Tile> {
+        let size: u32 = self.config.tile_size.max(1);
+        let mut tiles: Vec<Tile> = vec![];
+        for y in (0..self.image_height).step_by(size as usize) {
+            for x in (0..self.config.image_width).step_by(size as usize) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: size.min(self.config.image_width - x),
+                    height: size.min(self.image_height - y),
+                });
+            }
+        }
+        tiles
     }
 
-    fn get_ray(&self, col: u32, row: u32) -> Ray {
+    /// Returns the averaged samples of each pixel of `tile`, row by row.
+    /// The thread's generator is reseeded from the tile index, so the result doesn't depend on scheduling.
+    fn render_tile(&self, index: usize, tile: &Tile, scene: &Scene, integrator: &dyn Integrator, with_aovs: bool) -> Vec<Pixel> {
+        fastrand::seed(self.config.seed ^ (index as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+
+        let spp: f64 = self.config.samples_per_pixel.max(1) as f64;
+        let mut pixels: Vec<Pixel> = Vec::with_capacity((tile.width * tile.height) as usize);
+        for row in tile.y..tile.y + tile.height {
+            for col in tile.x..tile.x + tile.width {
+                let mut frag_sum_color: Color3 = Color3::new(0., 0., 0.);
+                let mut features: Option<Features> = None;
+                for _ in 0..self.config.samples_per_pixel {
+                    let r = self.get_ray(col, row);
+                    if !with_aovs {
+                        frag_sum_color += integrator.radiance(&r, scene);
+                    } else {
+                        let (color, hit) = integrator.radiance_and_hit(&r, scene);
+                        frag_sum_color += color;
+                        let sample: Features = hit.map_or_else(Features::default, |rec| self.features(&rec, scene));
+                        features = Some(match features {
+                            Some(sum) => sum.accumulate(&sample),
+                            None => sample,
+                        });
+                    }
+                }
+                pixels.push(Pixel {
+                    color: frag_sum_color / spp,
+                    features: features.map(|sum| sum.average(spp)),
+                });
+            }
+        }
+        pixels
+    }
+
+    /// The output variables of `rec`, the first hit of a primary ray.
+    fn features(&self, rec: &HitRecord, scene: &Scene) -> Features {
+        Features {
+            albedo: rec.mat.albedo(rec),
+            normal: rec.normal,
+            camera_normal: Vec3::new(rec.normal * self.u, rec.normal * self.v, rec.normal * self.w),
+            depth: rec.t,
+            position: rec.p,
+            material_id: scene.material_id(&rec.mat),
+            object_id: rec.object_id + 1,
+        }
+    }
+
+    /// Returns a ray going from the lens through a random point of the pixel at (`col`, `row`).
+    pub fn get_ray(&self, col: u32, row: u32) -> Ray {
         let frag_pos = self.pixel00 + (col as f64 + f64() - 0.5) * self.delta_u + (row as f64 + f64() - 0.5) * self.delta_v;
 
-        let ray_origin: Point3 = if self.defocus_angle <= 0.0 { self.center } else { self.defocus_ray_sample() };
+        let ray_origin: Point3 = if self.config.defocus_angle <= 0.0 { self.center } else { self.defocus_ray_sample() };
         let ray_dir = frag_pos - ray_origin;
 
-        Ray::new(ray_origin, ray_dir)
-
+        // The neighbouring pixels, whatever the sample count: finer offsets alias far textures.
+        Ray::new(ray_origin, ray_dir).with_differentials(Differentials {
+            rx_origin: ray_origin,
+            rx_dir: ray_dir + self.delta_u,
+            ry_origin: ray_origin,
+            ry_dir: ray_dir + self.delta_v,
+        })
     }
 
     fn defocus_ray_sample(&self) -> Point3 {
         let p = Vec3::rand_in_unit_disk();
         self.center + p.x() * self.defocus_disk_u + p.y() * self.defocus_disk_v
     }
-    
-    fn ray_color(&mut self, r: &Ray, depth: u32, objects: &Objects) -> Color3 {
-        if depth < 1 { return Color3::new(0., 0., 0.) }
+}
 
-        let mut rec: HitRecord = HitRecord::new();
-        if objects.hit(r, Interval::new(0.001, INFINITY), &mut rec) {
-            let mut r_scattered: Ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.));
-            let mut attenuation: Color3 = Color3::new(0., 0., 0.);
-            if rec.mat.scatter(r, &rec, &mut attenuation, &mut r_scattered) {
-                return attenuation.mult(self.ray_color(&r_scattered, depth-1, objects))
-            }
-            return Color3::new(0., 0., 0.)
+/// The result of a pixel, sent back by the workers.
+struct Pixel {
+    color: Color3,
+    features: Option<Features>,
+}
+
+/// Output variables of a primary ray, or their sum over the samples of a pixel.
+#[derive(Default)]
+struct Features {
+    albedo: Color3,
+    normal: Vec3,
+    camera_normal: Vec3,
+    depth: f64,
+    position: Point3,
+    material_id: u32,
+    object_id: u32,
+}
+
+impl Features {
+    /// Adds up the continuous values. IDs can't be mixed, so those of the first sample are kept.
+    fn accumulate(self, sample: &Features) -> Self {
+        Self {
+            albedo: self.albedo + sample.albedo,
+            normal: self.normal + sample.normal,
+            camera_normal: self.camera_normal + sample.camera_normal,
+            depth: self.depth + sample.depth,
+            position: self.position + sample.position,
+            ..self
         }
+    }
 
-        let unit: Vec3 = r.dir().unit();
-        let alpha: f64 = 0.5 * (unit.y() + 1.);
-        (1.-alpha) * Color3::new(1., 1., 1.) + alpha * Color3::new(1.0, 0.5, 0.8)
+    fn average(self, samples: f64) -> Self {
+        Self {
+            albedo: self.albedo / samples,
+            normal: self.normal / samples,
+            camera_normal: self.camera_normal / samples,
+            depth: self.depth / samples,
+            position: self.position / samples,
+            ..self
+        }
     }
 
-    fn write_color(&mut self, sum_color: Color3) {
-        let scale: f64 = 1. / self.samples_per_pixel as f64;
+    fn store(&self, aovs: &mut Aovs, x: u32, y: u32) {
+        let gray = |v: f64| Color3::new(v, v, v);
+        aovs.albedo.set(x, y, self.albedo);
+        aovs.normal.set(x, y, self.normal);
+        aovs.camera_normal.set(x, y, self.camera_normal);
+        aovs.depth.set(x, y, gray(self.depth));
+        aovs.position.set(x, y, self.position);
+        aovs.material_id.set(x, y, gray(self.material_id as f64));
+        aovs.object_id.set(x, y, gray(self.object_id as f64));
+    }
+}
+
+/// A rectangle of pixels rendered as one unit of work.
+struct Tile {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+
+// -------------------------------------------------- Tests
+
 
-        let mut pixel_color: Color3 = sum_color * scale;
-        pixel_color.to_gamma_2();
-        pixel_color.clamp(0., 1.);
+#[test]
+fn invalid_settings() {
+    let error = |config: CameraConfig| config.build().unwrap_err().to_string();
+    assert_eq!(error(CameraConfig { image_width: 0, ..Default::default() }), "invalid parameter: the image width must be at least 1");
+    assert!(error(CameraConfig { aspect: -1., ..Default::default() }).contains("aspect ratio"));
+    assert!(error(CameraConfig { lookat: CameraConfig::default().lookfrom, ..Default::default() }).contains("lookfrom and lookat"));
+    assert!(error(CameraConfig { vup: CameraConfig::default().lookat - CameraConfig::default().lookfrom, ..Default::default() }).contains("vup"));
+    assert!(error(CameraConfig { vup: Vec3::default(), ..Default::default() }).contains("vup"));
 
-        let _ = self.output.write(pixel_color.ppm().as_bytes()).unwrap();
+    let cam: Camera = CameraConfig { image_width: 4, samples_per_pixel: 1, ..Default::default() }.build().unwrap();
+    assert_eq!((cam.image_width(), cam.image_height()), (4, 2));
+}
+
+#[test]
+fn shared_camera() {
+    fn assert_sync<T: Send + Sync>(_: &T) {}
+    let cam: Camera = CameraConfig { image_width: 8, samples_per_pixel: 1, threads: 2, tile_size: 4, ..Default::default() }.build().unwrap();
+    assert_sync(&cam);
+
+    // Two renders at once from the same camera
+    let scene = Scene::new(crate::objects::Objects::new());
+    let integrator = crate::integrators::PathTracer::new(1);
+    let (a, b) = thread::scope(|s| {
+        let a = s.spawn(|| cam.render(&scene, &integrator));
+        let b = s.spawn(|| cam.render(&scene, &integrator));
+        (a.join().unwrap(), b.join().unwrap())
+    });
+    assert_eq!(a, b);
+
+    // Progress goes to the caller, one call per tile.
+    let calls = std::cell::RefCell::new(vec![]);
+    let c: Framebuffer = cam.render_with_progress(&scene, &integrator, None, &|done, total| calls.borrow_mut().push((done, total)));
+    assert_eq!(c, a);
+    assert_eq!(calls.into_inner(), [(1, 2), (2, 2)]);
+}
+#[test]
+fn output_variables() {
+    use std::sync::Arc;
+
+    use crate::image::{self, write_exr_layers, ExrOptions, ToneMapping};
+    use crate::integrators::PathTracer;
+    use crate::materials::Lambertian;
+    use crate::objects::{Objects, Quad};
+
+    // Two walls side by side at z = 0, on the focus plane: primary rays meet them at t = 1.
+    let (left, right) = (Color3::new(0.2, 0.4, 0.6), Color3::new(0.9, 0.1, 0.1));
+    let mut objects = Objects::new();
+    objects.add(Quad::new(Point3::new(-10., -10., 0.), Vec3::new(10., 0., 0.), Vec3::new(0., 20., 0.), Arc::new(Lambertian::new(left))).unwrap());
+    objects.add(Quad::new(Point3::new(0., -10., 0.), Vec3::new(10., 0., 0.), Vec3::new(0., 20., 0.), Arc::new(Lambertian::new(right))).unwrap());
+    let scene = Scene::new(objects);
+
+    let cam: Camera = CameraConfig {
+        aspect: 2.,
+        image_width: 8,
+        samples_per_pixel: 4,
+        lookfrom: Point3::new(0., 0., 5.),
+        focus_dist: 5.,
+        ..Default::default()
+    }.build().unwrap();
+    let (fb, aovs) = cam.render_with_aovs(&scene, &PathTracer::new(2));
+
+    let near = |a: Vec3, b: Vec3| (a - b).len() < 1e-9;
+    for (x, albedo, id) in [(1, left, 1.), (6, right, 2.)] {
+        assert!(near(aovs.albedo.get(x, 2), albedo));
+        assert!(near(aovs.normal.get(x, 2), Vec3::new(0., 0., 1.)));
+        assert!(near(aovs.camera_normal.get(x, 2), Vec3::new(0., 0., 1.)));
+        assert!(near(aovs.depth.get(x, 2), Vec3::new(1., 1., 1.)));
+        assert!(aovs.position.get(x, 2).z().abs() < 1e-9);
+        assert_eq!(aovs.material_id.get(x, 2), Color3::new(id, id, id));
+        assert_eq!(aovs.object_id.get(x, 2), Color3::new(id, id, id));
+    }
+
+    // The layers follow the color channels, in alphabetical order.
+    let mut exr: Vec<u8> = vec![];
+    write_exr_layers(&mut exr, &fb, &aovs, &ExrOptions::default()).unwrap();
+    let start: usize = exr.windows(16).position(|w| w == b"channels\0chlist\0").unwrap() + 20;
+    let mut names: Vec<String> = vec![];
+    let mut i: usize = start;
+    while exr[i] != 0 {
+        let end: usize = i + exr[i..].iter().position(|&b| b == 0).unwrap();
+        names.push(String::from_utf8(exr[i..end].to_vec()).unwrap());
+        i = end + 17;
     }
-}
\ No newline at end of file
+    assert_eq!(names, [
+        "B", "G", "N.X", "N.Y", "N.Z", "Nc.X", "Nc.Y", "Nc.Z", "P.X", "P.Y", "P.Z", "R", "Z",
+        "albedo.B", "albedo.G", "albedo.R", "materialID", "objectID",
+    ]);
+
+    // Other formats get an image per layer.
+    let dir: std::path::PathBuf = std::env::temp_dir().join("rustracer_aov_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    image::save_layers(&fb, &aovs, dir.join("out.hdr"), &ToneMapping::default(), &ExrOptions::default()).unwrap();
+    let albedo: crate::image::Framebuffer = image::load(dir.join("out.albedo.hdr")).unwrap();
+    assert!((albedo.get(6, 2) - right).len() < 0.01);
+    assert!(dir.join("out.objectID.hdr").exists());
+}
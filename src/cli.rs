@@ -0,0 +1,221 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use rustracer::image::{ExrCompression, ExrPixelType, ImageFormat};
+use rustracer::integrators::IntegratorKind;
+use rustracer::vec3::Vec3;
+use rustracer::{CameraConfig, SceneDescription};
+
+pub const USAGE: &str = "\
+Usage: rustracer <SCENE> [OPTIONS]
+
+Renders the JSON scene file SCENE. Options override the settings of the file.
+
+Options:
+  -w, --width <PIXELS>         Image width, the height following the aspect ratio
+  -s, --spp <N>                Samples per pixel
+  -d, --max-depth <N>          Maximum number of bounces
+  -o, --output <FILE>          Output image: .png, .ppm, .hdr or .exr
+      --seed <N>               Seed of the random generators and noise textures
+  -t, --threads <N>            Worker threads, 0 for all cores
+  -i, --integrator <NAME>      path, recursive, direct or ao
+  -c, --camera <FIELD=VALUE>   Sets a camera field, e.g. lookfrom=13,2,3 or vfov=30. Repeatable
+      --aovs                   Also writes the albedo, normals, depth, positions and IDs
+      --exr-pixel-type <TYPE>  Channels of OpenEXR outputs: half or float
+      --exr-compression <NAME> Compression of OpenEXR outputs: zip or none
+  -h, --help                   Prints this help
+";
+
+/// What the command line asks for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Render(Args),
+}
+
+/// The options of a render, each overriding the scene file when set.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Args {
+    pub scene: PathBuf,
+    pub width: Option<u32>,
+    pub spp: Option<u32>,
+    pub max_depth: Option<u32>,
+    pub output: Option<PathBuf>,
+    pub seed: Option<u64>,
+    pub threads: Option<usize>,
+    pub integrator: Option<IntegratorKind>,
+    pub aovs: bool,
+    pub exr_pixel_type: Option<ExrPixelType>,
+    pub exr_compression: Option<ExrCompression>,
+    /// Camera fields and their values, in the order given.
+    pub camera: Vec<(String, String)>,
+}
+
+impl Command {
+    /// Parses the arguments following the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut parsed = Args::default();
+        let mut scene: Option<PathBuf> = None;
+
+        while let Some(arg) = args.next() {
+            // Both `--opt value` and `--opt=value`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if arg.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            let mut value = || inline.clone().or_else(|| args.next()).ok_or_else(|| format!("missing value for {}", flag));
+
+            match flag.as_str() {
+                "-h" | "--help" => return Ok(Command::Help),
+                "-w" | "--width" => parsed.width = Some(positive(&flag, &value()?)?),
+                "-s" | "--spp" => parsed.spp = Some(positive(&flag, &value()?)?),
+                "-d" | "--max-depth" => parsed.max_depth = Some(number(&flag, &value()?)?),
+                "-o" | "--output" => parsed.output = Some(PathBuf::from(value()?)),
+                "--seed" => parsed.seed = Some(number(&flag, &value()?)?),
+                "--aovs" => parsed.aovs = true,
+                "-t" | "--threads" => parsed.threads = Some(number(&flag, &value()?)?),
+                "-i" | "--integrator" => {
+                    let name: String = value()?;
+                    parsed.integrator = Some(IntegratorKind::from_name(&name).ok_or_else(|| format!(
+                        "unknown integrator \"{}\", expected one of {}", name, IntegratorKind::NAMES.join(", "),
+                    ))?);
+                }
+                "--exr-pixel-type" => {
+                    let name: String = value()?;
+                    parsed.exr_pixel_type = Some(ExrPixelType::from_name(&name).ok_or_else(|| format!(
+                        "unknown EXR pixel type \"{}\", expected one of {}", name, ExrPixelType::NAMES.join(", "),
+                    ))?);
+                }
+                "--exr-compression" => {
+                    let name: String = value()?;
+                    parsed.exr_compression = Some(ExrCompression::from_name(&name).ok_or_else(|| format!(
+                        "unknown EXR compression \"{}\", expected one of {}", name, ExrCompression::NAMES.join(", "),
+                    ))?);
+                }
+                "-c" | "--camera" => {
+                    let setting: String = value()?;
+                    let (field, v) = setting.split_once('=').ok_or_else(|| format!("expected FIELD=VALUE for {}, found \"{}\"", flag, setting))?;
+                    // Checked now, so that errors show before the scene loads.
+                    set_camera_field(&mut CameraConfig::default(), field, v)?;
+                    parsed.camera.push((field.to_string(), v.to_string()));
+                }
+                _ if flag.starts_with('-') && flag.len() > 1 => return Err(format!("unknown option {}", flag)),
+                _ if scene.is_some() => return Err(format!("unexpected argument \"{}\", only one scene can be rendered", arg)),
+                _ => scene = Some(PathBuf::from(arg)),
+            }
+        }
+
+        parsed.scene = scene.ok_or("missing scene file")?;
+        Ok(Command::Render(parsed))
+    }
+}
+
+impl Args {
+    /// The seed set by `--seed` or `-c seed=N`, the latter taking precedence as in [`Args::apply`].
+    /// Needed before the scene loads, to seed its noise textures.
+    pub fn seed(&self) -> Option<u64> {
+        let camera = self.camera.iter().rev().find(|(field, _)| field == "seed");
+        camera.and_then(|(_, value)| value.trim().parse().ok()).or(self.seed)
+    }
+
+    /// Applies the overrides to a loaded scene.
+    pub fn apply(&self, desc: &mut SceneDescription) -> Result<(), String> {
+        let cam: &mut CameraConfig = &mut desc.camera;
+        if let Some(width) = self.width { cam.image_width = width }
+        if let Some(spp) = self.spp { cam.samples_per_pixel = spp }
+        if let Some(seed) = self.seed { cam.seed = seed }
+        if let Some(threads) = self.threads { cam.threads = threads }
+        for (field, value) in &self.camera {
+            set_camera_field(cam, field, value)?;
+        }
+
+        let settings = &mut desc.settings;
+        if let Some(max_depth) = self.max_depth { settings.max_depth = max_depth }
+        if let Some(output) = &self.output { settings.output = output.clone() }
+        if let Some(integrator) = self.integrator { settings.integrator = integrator }
+        if self.aovs { settings.aovs = true }
+        if let Some(pixel_type) = self.exr_pixel_type { settings.exr.pixel_type = pixel_type }
+        if let Some(compression) = self.exr_compression { settings.exr.compression = compression }
+
+        // Better now than after rendering.
+        if ImageFormat::from_path(&settings.output).is_none() {
+            return Err(format!("unknown image format for {}, expected .png, .ppm, .hdr or .exr", settings.output.display()));
+        }
+        Ok(())
+    }
+}
+
+/// Sets the field of `cam` named `field`, vectors being written as `x,y,z`.
+fn set_camera_field(cam: &mut CameraConfig, field: &str, value: &str) -> Result<(), String> {
+    let name: String = format!("camera {}", field);
+    match field {
+        "aspect" => cam.aspect = positive(&name, value)?,
+        "image_width" => cam.image_width = positive(&name, value)?,
+        "samples_per_pixel" => cam.samples_per_pixel = positive(&name, value)?,
+        "lookfrom" => cam.lookfrom = vec3(&name, value)?,
+        "lookat" => cam.lookat = vec3(&name, value)?,
+        "vup" => cam.vup = vec3(&name, value)?,
+        "vfov" => cam.vfov = number(&name, value)?,
+        "defocus_angle" => cam.defocus_angle = number(&name, value)?,
+        "focus_dist" => cam.focus_dist = positive(&name, value)?,
+        "threads" => cam.threads = number(&name, value)?,
+        "tile_size" => cam.tile_size = positive(&name, value)?,
+        "seed" => cam.seed = number(&name, value)?,
+        _ => return Err(format!(
+            "unknown camera field \"{}\", expected one of aspect, image_width, samples_per_pixel, lookfrom, lookat, vup, vfov, defocus_angle, focus_dist, threads, tile_size, seed",
+            field,
+        )),
+    }
+    Ok(())
+}
+
+fn number<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value.trim().parse().map_err(|_| format!("invalid value \"{}\" for {}", value, name))
+}
+
+/// A number above zero.
+fn positive<T: FromStr + PartialOrd + Default>(name: &str, value: &str) -> Result<T, String> {
+    let v: T = number(name, value)?;
+    if v > T::default() { Ok(v) } else { Err(format!("invalid value \"{}\" for {}: expected a positive number", value, name)) }
+}
+
+fn vec3(name: &str, value: &str) -> Result<Vec3, String> {
+    let parts: Vec<f64> = value.split(',').map(|v| number(name, v)).collect::<Result<_, _>>()?;
+    match parts[..] {
+        [x, y, z] => Ok(Vec3::new(x, y, z)),
+        _ => Err(format!("invalid value \"{}\" for {}: expected x,y,z", value, name)),
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn parse_arguments() {
+    let parse = |args: &str| Command::parse(args.split_whitespace().map(String::from));
+
+    let Ok(Command::Render(args)) = parse("scene.json -w 320 --spp=16 -i direct -c lookfrom=1,2,3 --camera vfov=40 -o out.exr --aovs --exr-pixel-type float --exr-compression=none") else { panic!() };
+    assert_eq!(args.scene, PathBuf::from("scene.json"));
+    assert_eq!((args.width, args.spp, args.integrator), (Some(320), Some(16), Some(IntegratorKind::Direct)));
+    assert_eq!(args.camera, [("lookfrom".to_string(), "1,2,3".to_string()), ("vfov".to_string(), "40".to_string())]);
+    assert_eq!(args.output, Some(PathBuf::from("out.exr")));
+    assert!(args.aovs);
+    assert_eq!((args.exr_pixel_type, args.exr_compression), (Some(ExrPixelType::Float), Some(ExrCompression::None)));
+    assert_eq!(args.seed(), None);
+    let Ok(Command::Render(args)) = parse("scene.json --seed 3 -c seed=5") else { panic!() };
+    assert_eq!(args.seed(), Some(5));
+
+    assert_eq!(parse("scene.json --help"), Ok(Command::Help));
+    assert_eq!(parse("-w 100"), Err("missing scene file".to_string()));
+    assert_eq!(parse("scene.json --spp"), Err("missing value for --spp".to_string()));
+    assert_eq!(parse("scene.json --spp 0"), Err("invalid value \"0\" for --spp: expected a positive number".to_string()));
+    assert_eq!(parse("scene.json --width abc"), Err("invalid value \"abc\" for --width".to_string()));
+    assert_eq!(parse("scene.json --fast"), Err("unknown option --fast".to_string()));
+    assert_eq!(parse("a.json b.json"), Err("unexpected argument \"b.json\", only one scene can be rendered".to_string()));
+    assert_eq!(parse("scene.json -c lookfrom=1,2"), Err("invalid value \"1,2\" for camera lookfrom: expected x,y,z".to_string()));
+    assert!(parse("scene.json -c fov=30").unwrap_err().starts_with("unknown camera field \"fov\""));
+    assert!(parse("scene.json -i bdpt").unwrap_err().starts_with("unknown integrator \"bdpt\""));
+    assert!(parse("scene.json --exr-pixel-type double").unwrap_err().starts_with("unknown EXR pixel type \"double\""));
+}
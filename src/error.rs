@@ -0,0 +1,59 @@
+use std::fmt;
+use std::io;
+
+use crate::loaders::LoadError;
+
+/// Everything that can go wrong when loading, rendering or saving.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading or writing a file failed.
+    Io(io::Error),
+    /// The scene file is invalid.
+    Scene(LoadError),
+    /// A setting can't be used, like a zero image width.
+    InvalidParameter(String),
+    /// A mesh or other file the scene refers to couldn't be loaded.
+    Asset(LoadError),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn invalid_parameter(message: impl Into<String>) -> Self {
+        Error::InvalidParameter(message.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Scene(e) => write!(f, "invalid scene: {}", e),
+            Error::InvalidParameter(message) => write!(f, "invalid parameter: {}", message),
+            Error::Asset(e) => write!(f, "couldn't load asset: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Scene(e) | Error::Asset(e) => Some(e),
+            Error::InvalidParameter(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Assets are loaded on their own with [`crate::loaders::load_obj`] and the like.
+impl From<LoadError> for Error {
+    fn from(e: LoadError) -> Self {
+        Error::Asset(e)
+    }
+}
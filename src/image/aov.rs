@@ -0,0 +1,50 @@
+use super::Framebuffer;
+
+/// Arbitrary output variables: what the camera sees first through each pixel, for denoising and compositing.
+/// Each is averaged over the samples of the pixel, except the IDs, taken from the first sample.
+/// Single values are stored in all three channels. Pixels where rays escape are 0 everywhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aovs {
+    /// Base color of the material.
+    pub albedo: Framebuffer,
+    /// World space shading normal, facing the camera.
+    pub normal: Framebuffer,
+    /// Shading normal in the camera frame: x right, y up, z toward the camera.
+    pub camera_normal: Framebuffer,
+    /// The t value of the hit along the primary ray, which goes from the lens to the focus plane for t = 1.
+    pub depth: Framebuffer,
+    /// World space position.
+    pub position: Framebuffer,
+    /// ID of the material, numbered from 1 by the scene.
+    pub material_id: Framebuffer,
+    /// ID of the object, numbered from 1 in the order objects were added.
+    pub object_id: Framebuffer,
+}
+
+impl Aovs {
+    pub fn new(width: u32, height: u32) -> Self {
+        let fb = || Framebuffer::new(width, height);
+        Self {
+            albedo: fb(),
+            normal: fb(),
+            camera_normal: fb(),
+            depth: fb(),
+            position: fb(),
+            material_id: fb(),
+            object_id: fb(),
+        }
+    }
+
+    /// Each output with its name, and whether it holds a single value.
+    pub fn layers(&self) -> [(&'static str, &Framebuffer, bool); 7] {
+        [
+            ("albedo", &self.albedo, false),
+            ("N", &self.normal, false),
+            ("Nc", &self.camera_normal, false),
+            ("Z", &self.depth, true),
+            ("P", &self.position, false),
+            ("materialID", &self.material_id, true),
+            ("objectID", &self.object_id, true),
+        ]
+    }
+}
@@ -0,0 +1,206 @@
+use std::thread;
+
+use super::{Aovs, Framebuffer};
+use crate::vec3::{Color3, Vec3};
+
+/// B3 spline, the smoothing kernel of each à-trous pass.
+const KERNEL: [f64; 5] = [1. / 16., 1. / 4., 3. / 8., 1. / 4., 1. / 16.];
+
+/// Edge-avoiding à-trous wavelet filter (Dammertz et al. 2010), guided by the output variables.
+/// Lighting is filtered apart from the albedo, so that textures stay sharp.
+///
+/// ```no_run
+/// # use rustracer::{CameraConfig, Scene, objects::Objects, integrators::PathTracer};
+/// use rustracer::image::{self, Denoiser, ExrOptions, ToneMapping};
+///
+/// # let scene = Scene::new(Objects::new());
+/// let cam = CameraConfig { samples_per_pixel: 4, ..Default::default() }.build()?;
+/// let (fb, aovs) = cam.render_with_aovs(&scene, &PathTracer::new(8));
+/// let denoised = Denoiser::default().denoise(&fb, &aovs);
+/// image::save(&denoised, "preview.png", &ToneMapping::default(), &ExrOptions::default())?;
+/// # Ok::<(), rustracer::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Denoiser {
+    /// Number of passes. Each doubles the spacing of the taps, so 5 passes cover 125 pixels.
+    pub iterations: u32,
+    /// Tolerance to differences of lighting, halved after each pass.
+    pub sigma_color: f64,
+    /// Exponent of the cosine between normals: the higher, the sharper the geometric edges.
+    pub sigma_normal: f64,
+    /// Tolerance to relative differences of depth, per pixel of distance.
+    pub sigma_depth: f64,
+    /// Tolerance to differences of albedo.
+    pub sigma_albedo: f64,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self {
+            iterations: 5,
+            sigma_color: 4.,
+            sigma_normal: 64.,
+            sigma_depth: 0.02,
+            sigma_albedo: 0.2,
+        }
+    }
+}
+
+impl Denoiser {
+    /// Returns a filtered copy of `fb`, rendered along with `aovs`.
+    pub fn denoise(&self, fb: &Framebuffer, aovs: &Aovs) -> Framebuffer {
+        let guides = Guides {
+            width: fb.width() as i64,
+            height: fb.height() as i64,
+            albedo: aovs.albedo.pixels(),
+            normal: aovs.normal.pixels(),
+            depth: aovs.depth.pixels().iter().map(|d| d.x()).collect(),
+        };
+
+        let mut lighting: Vec<Color3> = fb.pixels().iter().zip(guides.albedo).map(|(&c, &a)| demodulate(c, a)).collect();
+        let mut next: Vec<Color3> = lighting.clone();
+        let threads: usize = thread::available_parallelism().map_or(1, |n| n.get());
+        let band: usize = (fb.height() as usize).div_ceil(threads).max(1);
+        let width: usize = fb.width() as usize;
+
+        for i in 0..self.iterations {
+            let step: i64 = 1 << i;
+            // Rows are split among threads, each writing its own band: the result doesn't depend on their count.
+            thread::scope(|s| {
+                for (b, rows) in next.chunks_mut(band * width).enumerate() {
+                    let (lighting, guides) = (&lighting, &guides);
+                    s.spawn(move || {
+                        for (r, row) in rows.chunks_mut(width).enumerate() {
+                            for (x, out) in row.iter_mut().enumerate() {
+                                *out = self.filter(x as i64, (b * band + r) as i64, step, lighting, guides);
+                            }
+                        }
+                    });
+                }
+            });
+            std::mem::swap(&mut lighting, &mut next);
+        }
+
+        let mut out = Framebuffer::new(fb.width(), fb.height());
+        for (i, (&l, &a)) in lighting.iter().zip(guides.albedo).enumerate() {
+            out.set(i as u32 % fb.width(), i as u32 / fb.width(), remodulate(l, a));
+        }
+        out
+    }
+
+    /// Filters the pixel at (`x`, `y`) with taps `step` pixels apart.
+    fn filter(&self, x: i64, y: i64, step: i64, lighting: &[Color3], guides: &Guides) -> Color3 {
+        let sigma_color: f64 = self.sigma_color / step as f64;
+        let p: usize = (y * guides.width + x) as usize;
+        let mut sum: Color3 = Color3::default();
+        let mut weights: f64 = 0.;
+
+        for (ky, hy) in KERNEL.iter().enumerate() {
+            let qy: i64 = y + (ky as i64 - 2) * step;
+            if qy < 0 || qy >= guides.height {
+                continue;
+            }
+            for (kx, hx) in KERNEL.iter().enumerate() {
+                let qx: i64 = x + (kx as i64 - 2) * step;
+                if qx < 0 || qx >= guides.width {
+                    continue;
+                }
+                let q: usize = (qy * guides.width + qx) as usize;
+
+                let w_geometry: f64 = self.geometry_weight(guides.normal[p], guides.normal[q], guides.depth[p], guides.depth[q], step);
+                if w_geometry <= 0. {
+                    continue;
+                }
+                let d_color: Vec3 = lighting[p] - lighting[q];
+                let d_albedo: Vec3 = guides.albedo[p] - guides.albedo[q];
+                let w: f64 = hx * hy * w_geometry
+                    * (-d_color.len_squared() / (sigma_color * sigma_color)).exp()
+                    * (-d_albedo.len_squared() / (self.sigma_albedo * self.sigma_albedo)).exp();
+
+                sum += w * lighting[q];
+                weights += w;
+            }
+        }
+
+        // The center tap always has a weight, unless it holds NaNs.
+        if weights > 0. { sum / weights } else { lighting[p] }
+    }
+
+    /// How much two pixels are on the same surface. Pixels where rays escape only mix together.
+    fn geometry_weight(&self, n_p: Vec3, n_q: Vec3, z_p: f64, z_q: f64, step: i64) -> f64 {
+        let (miss_p, miss_q) = (n_p.len_squared() < 1e-6, n_q.len_squared() < 1e-6);
+        if miss_p || miss_q {
+            return if miss_p == miss_q { 1. } else { 0. };
+        }
+
+        let w_normal: f64 = (n_p * n_q).max(0.).powf(self.sigma_normal);
+        let w_depth: f64 = (-(z_p - z_q).abs() / (self.sigma_depth * z_p * step as f64 + 1e-9)).exp();
+        w_normal * w_depth
+    }
+}
+
+/// The feature buffers steering the filter.
+struct Guides<'a> {
+    width: i64,
+    height: i64,
+    albedo: &'a [Color3],
+    normal: &'a [Vec3],
+    depth: Vec<f64>,
+}
+
+/// Divides the albedo out of a color, leaving the lighting.
+fn demodulate(color: Color3, albedo: Color3) -> Color3 {
+    let a = |v: f64| v.max(0.01);
+    Color3::new(color.x() / a(albedo.x()), color.y() / a(albedo.y()), color.z() / a(albedo.z()))
+}
+
+fn remodulate(lighting: Color3, albedo: Color3) -> Color3 {
+    let a = |v: f64| v.max(0.01);
+    Color3::new(lighting.x() * a(albedo.x()), lighting.y() * a(albedo.y()), lighting.z() * a(albedo.z()))
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn denoise_keeps_edges() {
+    // Two noisy walls facing different ways, side by side.
+    fastrand::seed(5);
+    let (width, height) = (64, 32);
+    let mut fb = Framebuffer::new(width, height);
+    let mut aovs = Aovs::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let left: bool = x < width / 2;
+            let level: f64 = if left { 0.2 } else { 0.8 };
+            let noisy: f64 = level * 2. * fastrand::f64();
+            fb.set(x, y, Color3::new(noisy, noisy, noisy));
+            aovs.albedo.set(x, y, Color3::new(1., 1., 1.));
+            aovs.normal.set(x, y, if left { Vec3::new(0., 0., 1.) } else { Vec3::new(1., 0., 0.) });
+            aovs.depth.set(x, y, Color3::new(5., 5., 5.));
+        }
+    }
+
+    let denoised: Framebuffer = Denoiser::default().denoise(&fb, &aovs);
+    let error = |img: &Framebuffer| -> f64 {
+        let mut sum: f64 = 0.;
+        for y in 0..height {
+            for x in 0..width {
+                let level: f64 = if x < width / 2 { 0.2 } else { 0.8 };
+                sum += (img.get(x, y).x() - level).powi(2);
+            }
+        }
+        (sum / (width * height) as f64).sqrt()
+    };
+    assert!(error(&denoised) < 0.25 * error(&fb));
+
+    // Nothing leaks across the edge.
+    for y in 0..height {
+        assert!((denoised.get(width / 2 - 1, y).x() - 0.2).abs() < 0.1);
+        assert!((denoised.get(width / 2, y).x() - 0.8).abs() < 0.2);
+    }
+
+    // Deterministic
+    assert_eq!(denoised, Denoiser::default().denoise(&fb, &aovs));
+}
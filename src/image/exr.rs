@@ -0,0 +1,357 @@
+use std::io::{self, Write};
+
+use super::{zlib, Aovs, Framebuffer};
+
+const MAGIC: [u8; 4] = [0x76, 0x2F, 0x31, 0x01];
+
+/// How the channels of an OpenEXR image are stored.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExrPixelType {
+    /// 16-bit floats.
+    Half,
+    /// 32-bit floats.
+    Float,
+}
+
+impl ExrPixelType {
+    pub const NAMES: [&'static str; 2] = ["half", "float"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "half" => Some(Self::Half),
+            "float" => Some(Self::Float),
+            _ => None,
+        }
+    }
+}
+
+/// The OpenEXR compression schemes that can be written.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExrCompression {
+    None,
+    /// Deflate over blocks of 16 scanlines.
+    Zip,
+}
+
+impl ExrCompression {
+    pub const NAMES: [&'static str; 2] = ["none", "zip"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Self::None),
+            "zip" => Some(Self::Zip),
+            _ => None,
+        }
+    }
+
+    fn id(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zip => 3,
+        }
+    }
+
+    fn lines_per_block(self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::Zip => 16,
+        }
+    }
+}
+
+/// How OpenEXR images are written, half floats with ZIP compression by default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExrOptions {
+    pub pixel_type: ExrPixelType,
+    pub compression: ExrCompression,
+}
+
+impl Default for ExrOptions {
+    fn default() -> Self {
+        Self {
+            pixel_type: ExrPixelType::Half,
+            compression: ExrCompression::Zip,
+        }
+    }
+}
+
+/// Writes `fb` as a single-part scanline OpenEXR image with linear R, G and B channels.
+pub fn write_exr(out: &mut impl Write, fb: &Framebuffer, options: &ExrOptions) -> io::Result<()> {
+    let channels: Vec<Channel> = ["R", "G", "B"].iter().enumerate().map(|(i, &name)| (name.to_string(), fb, i)).collect();
+    write_channels(out, fb.width(), fb.height(), channels, options)
+}
+
+/// Same as [`write_exr`], with each of `aovs` as an extra layer: `albedo.R`, `N.X`, `Z`, and so on.
+/// IDs above 2048 aren't exact with half channels.
+pub fn write_exr_layers(out: &mut impl Write, fb: &Framebuffer, aovs: &Aovs, options: &ExrOptions) -> io::Result<()> {
+    let mut channels: Vec<Channel> = ["R", "G", "B"].iter().enumerate().map(|(i, &name)| (name.to_string(), fb, i)).collect();
+    for (layer, buffer, single) in aovs.layers() {
+        if single {
+            channels.push((layer.to_string(), buffer, 0));
+            continue;
+        }
+        let components: [&str; 3] = if layer == "albedo" { ["R", "G", "B"] } else { ["X", "Y", "Z"] };
+        for (i, component) in components.iter().enumerate() {
+            channels.push((format!("{}.{}", layer, component), buffer, i));
+        }
+    }
+    write_channels(out, fb.width(), fb.height(), channels, options)
+}
+
+/// A channel name, the image it comes from and the component of the colors it holds.
+type Channel<'a> = (String, &'a Framebuffer, usize);
+
+fn write_channels(out: &mut impl Write, width: u32, height: u32, mut channels: Vec<Channel>, options: &ExrOptions) -> io::Result<()> {
+    let (width, height) = (width as usize, height as usize);
+
+    let mut header: Vec<u8> = vec![];
+    header.extend(MAGIC);
+    header.extend(2u32.to_le_bytes());
+
+    // Channels are stored in alphabetical order.
+    channels.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut chlist: Vec<u8> = vec![];
+    for (name, _, _) in &channels {
+        chlist.extend(name.bytes());
+        chlist.push(0);
+        let pixel_type: i32 = match options.pixel_type {
+            ExrPixelType::Half => 1,
+            ExrPixelType::Float => 2,
+        };
+        chlist.extend(pixel_type.to_le_bytes());
+        // pLinear, reserved, x and y sampling
+        chlist.extend([0; 4]);
+        chlist.extend(1i32.to_le_bytes());
+        chlist.extend(1i32.to_le_bytes());
+    }
+    chlist.push(0);
+
+    let window: Vec<u8> = [0, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+    write_attribute(&mut header, "channels", "chlist", &chlist);
+    write_attribute(&mut header, "compression", "compression", &[options.compression.id()]);
+    write_attribute(&mut header, "dataWindow", "box2i", &window);
+    write_attribute(&mut header, "displayWindow", "box2i", &window);
+    write_attribute(&mut header, "lineOrder", "lineOrder", &[0]);
+    write_attribute(&mut header, "pixelAspectRatio", "float", &1f32.to_le_bytes());
+    write_attribute(&mut header, "screenWindowCenter", "v2f", &[0; 8]);
+    write_attribute(&mut header, "screenWindowWidth", "float", &1f32.to_le_bytes());
+    header.push(0);
+
+    let lines: usize = options.compression.lines_per_block();
+    let chunks: Vec<Vec<u8>> = (0..height).step_by(lines).map(|y| {
+        let mut data: Vec<u8> = vec![];
+        for row in y..(y + lines).min(height) {
+            for (_, fb, component) in &channels {
+                for color in &fb.pixels()[row * width..(row + 1) * width] {
+                    let v: f32 = color.axis(*component) as f32;
+                    match options.pixel_type {
+                        ExrPixelType::Half => data.extend(f32_to_half(v).to_le_bytes()),
+                        ExrPixelType::Float => data.extend(v.to_le_bytes()),
+                    }
+                }
+            }
+        }
+
+        let data: Vec<u8> = match options.compression {
+            ExrCompression::None => data,
+            // Blocks that don't shrink are stored raw, readers tell them apart by their size.
+            ExrCompression::Zip => {
+                let compressed: Vec<u8> = zlib::compress(&predict(&data));
+                if compressed.len() < data.len() { compressed } else { data }
+            }
+        };
+
+        let mut chunk: Vec<u8> = vec![];
+        chunk.extend((y as i32).to_le_bytes());
+        chunk.extend((data.len() as i32).to_le_bytes());
+        chunk.extend(data);
+        chunk
+    }).collect();
+
+    // The offset table points to each chunk from the start of the file.
+    let mut offset: u64 = (header.len() + 8 * chunks.len()) as u64;
+    out.write_all(&header)?;
+    for chunk in &chunks {
+        out.write_all(&offset.to_le_bytes())?;
+        offset += chunk.len() as u64;
+    }
+    for chunk in &chunks {
+        out.write_all(chunk)?;
+    }
+    Ok(())
+}
+
+fn write_attribute(header: &mut Vec<u8>, name: &str, kind: &str, value: &[u8]) {
+    for s in [name, kind] {
+        header.extend(s.bytes());
+        header.push(0);
+    }
+    header.extend((value.len() as i32).to_le_bytes());
+    header.extend(value);
+}
+
+/// The ZIP preprocessing: bytes are split into even and odd halves, then delta-encoded.
+fn predict(data: &[u8]) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(data.len());
+    out.extend(data.iter().step_by(2));
+    out.extend(data.iter().skip(1).step_by(2));
+
+    let mut prev: u8 = out.first().copied().unwrap_or(0);
+    for b in out.iter_mut().skip(1) {
+        let cur: u8 = *b;
+        *b = cur.wrapping_sub(prev).wrapping_add(128);
+        prev = cur;
+    }
+    out
+}
+
+/// Converts to the nearest 16-bit float, ties to even. Out of range values become infinite.
+pub(crate) fn f32_to_half(v: f32) -> u16 {
+    let bits: u32 = v.to_bits();
+    let sign: u16 = ((bits >> 16) & 0x8000) as u16;
+    let exp: i32 = ((bits >> 23) & 0xFF) as i32;
+    let mantissa: u32 = bits & 0x7F_FFFF;
+
+    if exp == 0xFF {
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+
+    let e: i32 = exp - 127 + 15;
+    if e >= 31 {
+        return sign | 0x7C00;
+    }
+    if e <= 0 {
+        // Subnormal
+        if e < -10 {
+            return sign;
+        }
+        let m: u32 = mantissa | 0x80_0000;
+        let shift: u32 = (14 - e) as u32;
+        let (half, rest, halfway) = (m >> shift, m & ((1 << shift) - 1), 1 << (shift - 1));
+        let rounded: u32 = if rest > halfway || (rest == halfway && half & 1 == 1) { half + 1 } else { half };
+        return sign | rounded as u16;
+    }
+
+    let half: u16 = sign | ((e as u16) << 10) | (mantissa >> 13) as u16;
+    let rest: u32 = mantissa & 0x1FFF;
+    // A carry into the exponent is still the right rounding.
+    if rest > 0x1000 || (rest == 0x1000 && half & 1 == 1) { half + 1 } else { half }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn half_reference() {
+    assert_eq!(f32_to_half(0.), 0x0000);
+    assert_eq!(f32_to_half(-0.), 0x8000);
+    assert_eq!(f32_to_half(1.), 0x3C00);
+    assert_eq!(f32_to_half(0.5), 0x3800);
+    assert_eq!(f32_to_half(-2.), 0xC000);
+    assert_eq!(f32_to_half(65504.), 0x7BFF);
+    assert_eq!(f32_to_half(1e6), 0x7C00);
+    assert_eq!(f32_to_half(2f32.powi(-24)), 0x0001);
+    assert_eq!(f32_to_half(2f32.powi(-14)), 0x0400);
+    assert_eq!(f32_to_half(1. + 2f32.powi(-11)), 0x3C00);
+    assert_eq!(f32_to_half(1. + 3. * 2f32.powi(-11)), 0x3C02);
+    assert_eq!(f32_to_half(f32::NAN) & 0x7C00, 0x7C00);
+}
+
+#[test]
+fn exr_structure() {
+    use crate::vec3::Color3;
+
+    let (width, height): (usize, usize) = (5, 20);
+    let mut fb = Framebuffer::new(width as u32, height as u32);
+    for y in 0..height {
+        for x in 0..width {
+            fb.set(x as u32, y as u32, Color3::new(x as f64 * 0.25, 1., if y < 16 { 0.5 } else { 2. }));
+        }
+    }
+
+    for pixel_type in [ExrPixelType::Half, ExrPixelType::Float] {
+        for compression in [ExrCompression::None, ExrCompression::Zip] {
+            let mut data: Vec<u8> = vec![];
+            write_exr(&mut data, &fb, &ExrOptions { pixel_type, compression }).unwrap();
+            let u32_at = |pos: usize| u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+            assert_eq!(data[..4], MAGIC);
+            assert_eq!(u32_at(4), 2);
+
+            // Attributes are a name, a type, a size and a value, up to an empty name.
+            let mut pos: usize = 8;
+            let mut attributes: Vec<(String, String, Vec<u8>)> = vec![];
+            let string = |pos: &mut usize| {
+                let end: usize = *pos + data[*pos..].iter().position(|&b| b == 0).unwrap();
+                let s: String = String::from_utf8(data[*pos..end].to_vec()).unwrap();
+                *pos = end + 1;
+                s
+            };
+            while data[pos] != 0 {
+                let (name, kind) = (string(&mut pos), string(&mut pos));
+                let size: usize = u32_at(pos) as usize;
+                attributes.push((name, kind, data[pos + 4..pos + 4 + size].to_vec()));
+                pos += 4 + size;
+            }
+            pos += 1;
+            let names: Vec<&str> = attributes.iter().map(|(name, _, _)| name.as_str()).collect();
+            assert_eq!(names, ["channels", "compression", "dataWindow", "displayWindow", "lineOrder", "pixelAspectRatio", "screenWindowCenter", "screenWindowWidth"]);
+            assert_eq!(attributes[1].2, [compression.id()]);
+            let window: Vec<u8> = [0i32, 0, width as i32 - 1, height as i32 - 1].iter().flat_map(|v| v.to_le_bytes()).collect();
+            assert_eq!((attributes[2].1.as_str(), &attributes[2].2), ("box2i", &window));
+
+            // Each channel is a name and its pixel type, followed by 12 bytes of flags and sampling.
+            let chlist: &[u8] = &attributes[0].2;
+            let channel_type: i32 = if pixel_type == ExrPixelType::Half { 1 } else { 2 };
+            let mut expected: Vec<u8> = vec![];
+            for name in ["B", "G", "R"] {
+                expected.extend([name.as_bytes()[0], 0]);
+                expected.extend(channel_type.to_le_bytes());
+                expected.extend([0, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 0]);
+            }
+            expected.push(0);
+            assert_eq!(chlist, expected);
+
+            // The offset table points to consecutive chunks, up to the end of the file.
+            let lines: usize = compression.lines_per_block();
+            let chunks: usize = height.div_ceil(lines);
+            let offsets: Vec<usize> = (0..chunks).map(|i| u64::from_le_bytes(data[pos + 8 * i..pos + 8 * i + 8].try_into().unwrap()) as usize).collect();
+            assert_eq!(offsets[0], pos + 8 * chunks);
+            let sample_size: usize = if pixel_type == ExrPixelType::Half { 2 } else { 4 };
+            for (i, &offset) in offsets.iter().enumerate() {
+                let (y, size) = (u32_at(offset) as usize, u32_at(offset + 4) as usize);
+                assert_eq!(y, i * lines);
+                assert_eq!(offsets.get(i + 1).copied().unwrap_or(data.len()), offset + 8 + size);
+
+                // Scanlines hold each channel in turn, ZIP chunks are deflated and predicted.
+                let rows: usize = lines.min(height - y);
+                let raw_size: usize = rows * 3 * width * sample_size;
+                let mut pixels: Vec<u8> = data[offset + 8..offset + 8 + size].to_vec();
+                if compression == ExrCompression::Zip && size < raw_size {
+                    let mut predicted: Vec<u8> = zlib::decompress(&pixels).unwrap();
+                    for j in 1..predicted.len() {
+                        predicted[j] = predicted[j].wrapping_add(predicted[j - 1]).wrapping_sub(128);
+                    }
+                    let (even, odd) = predicted.split_at(raw_size.div_ceil(2));
+                    pixels = (0..raw_size).map(|j| if j % 2 == 0 { even[j / 2] } else { odd[j / 2] }).collect();
+                }
+                // The first chunk is smooth enough to shrink.
+                assert!(compression == ExrCompression::None || i > 0 || size < raw_size);
+
+                let mut expected: Vec<u8> = vec![];
+                for row in y..y + rows {
+                    for component in [2, 1, 0] {
+                        for x in 0..width {
+                            let v: f32 = fb.get(x as u32, row as u32).axis(component) as f32;
+                            match pixel_type {
+                                ExrPixelType::Half => expected.extend(f32_to_half(v).to_le_bytes()),
+                                ExrPixelType::Float => expected.extend(v.to_le_bytes()),
+                            }
+                        }
+                    }
+                }
+                assert_eq!(pixels, expected);
+            }
+        }
+    }
+}
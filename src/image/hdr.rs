@@ -0,0 +1,210 @@
+use std::io::{self, Write};
+
+use super::Framebuffer;
+use crate::vec3::Color3;
+
+/// Writes `fb` as a Radiance RGBE (.hdr) image, keeping the linear radiance values.
+/// Scanlines are run-length encoded when their width allows it.
+pub fn write_hdr(out: &mut impl Write, fb: &Framebuffer) -> io::Result<()> {
+    let (width, height) = (fb.width() as usize, fb.height() as usize);
+    write!(out, "#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n", height, width)?;
+
+    let mut line: Vec<u8> = Vec::with_capacity(4 * width + 4);
+    for row in fb.pixels().chunks(width.max(1)) {
+        line.clear();
+        let rgbe: Vec<[u8; 4]> = row.iter().map(|&c| to_rgbe(c)).collect();
+        // Flat scanlines for widths the RLE scheme can't describe.
+        if !(8..=0x7FFF).contains(&width) {
+            line.extend(rgbe.iter().flatten());
+        } else {
+            line.extend([2, 2, (width >> 8) as u8, width as u8]);
+            for component in 0..4 {
+                let channel: Vec<u8> = rgbe.iter().map(|p| p[component]).collect();
+                run_length(&channel, &mut line);
+            }
+        }
+        out.write_all(&line)?;
+    }
+    Ok(())
+}
+
+/// Shared exponent encoding of a color. Negative components are clamped to 0.
+pub(crate) fn to_rgbe(color: Color3) -> [u8; 4] {
+    let [r, g, b] = [color.x(), color.y(), color.z()].map(|v| v.max(0.));
+    let v: f64 = r.max(g).max(b);
+    if v < 1e-32 || !v.is_finite() {
+        return [0; 4];
+    }
+
+    // v = m * 2^e with m in [0.5, 1)
+    let mut e: i32 = v.log2().floor() as i32 + 1;
+    if v / 2f64.powi(e) >= 1. {
+        e += 1;
+    }
+    let scale: f64 = 256. / 2f64.powi(e);
+    [(r * scale) as u8, (g * scale) as u8, (b * scale) as u8, (e + 128).clamp(0, 255) as u8]
+}
+
+/// Reads a Radiance RGBE (.hdr) image, with run-length encoded scanlines or not.
+/// Only the usual `-Y height +X width` orientation is supported.
+pub fn read_hdr(data: &[u8]) -> io::Result<Framebuffer> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if !data.starts_with(b"#?") {
+        return Err(invalid("not a Radiance HDR file"));
+    }
+
+    // Header lines up to an empty one, then the resolution line.
+    let mut lines = data.split(|&b| b == b'\n');
+    let mut header_len: usize = 0;
+    for line in lines.by_ref() {
+        header_len += line.len() + 1;
+        if line.starts_with(b"FORMAT=") && line != b"FORMAT=32-bit_rle_rgbe" {
+            return Err(invalid("unsupported pixel format, expected 32-bit_rle_rgbe"));
+        }
+        if line.is_empty() {
+            break;
+        }
+    }
+    let resolution: &[u8] = lines.next().ok_or_else(|| invalid("missing resolution"))?;
+    header_len += resolution.len() + 1;
+    let resolution: String = String::from_utf8_lossy(resolution).into_owned();
+    let (height, width) = match resolution.split_whitespace().collect::<Vec<&str>>()[..] {
+        ["-Y", h, "+X", w] => (h.parse::<u32>().ok(), w.parse::<u32>().ok()),
+        _ => return Err(invalid("unsupported orientation, expected -Y height +X width")),
+    };
+    let (Some(width), Some(height)) = (width, height) else { return Err(invalid("invalid resolution")) };
+
+    // The shortest scanlines are run-length encoded with runs of 127 pixels, 2 bytes each per channel.
+    let pos: usize = header_len.min(data.len());
+    let line_len: usize = if (8..=0x7FFF).contains(&width) { 4 + 8 * (width as usize).div_ceil(127) } else { 4 * width as usize };
+    if (height as usize).checked_mul(line_len).is_none_or(|len| data.len() - pos < len) {
+        return Err(invalid("truncated or invalid scanline"));
+    }
+
+    let mut fb = Framebuffer::new(width, height);
+    let mut pos: usize = pos;
+    let mut line: Vec<[u8; 4]> = vec![[0; 4]; width as usize];
+    for y in 0..height {
+        pos = read_scanline(data, pos, &mut line).ok_or_else(|| invalid("truncated or invalid scanline"))?;
+        for (x, &rgbe) in line.iter().enumerate() {
+            fb.set(x as u32, y, from_rgbe(rgbe));
+        }
+    }
+    Ok(fb)
+}
+
+/// Reads the scanline at `pos` into `line`, returning the position of the next one.
+fn read_scanline(data: &[u8], mut pos: usize, line: &mut [[u8; 4]]) -> Option<usize> {
+    let width: usize = line.len();
+    let start: &[u8] = data.get(pos..pos + 4)?;
+    let run_length: bool = (8..=0x7FFF).contains(&width) && start[0] == 2 && start[1] == 2 && start[2] & 0x80 == 0;
+    if !run_length {
+        // Flat pixels. Old-style runs, marked with (1, 1, 1, count), are rare enough to be refused.
+        for pixel in line.iter_mut() {
+            let rgbe: &[u8] = data.get(pos..pos + 4)?;
+            if rgbe[..3] == [1, 1, 1] {
+                return None;
+            }
+            pixel.copy_from_slice(rgbe);
+            pos += 4;
+        }
+        return Some(pos);
+    }
+
+    if ((start[2] as usize) << 8 | start[3] as usize) != width {
+        return None;
+    }
+    pos += 4;
+    for component in 0..4 {
+        let mut x: usize = 0;
+        while x < width {
+            let count: usize = *data.get(pos)? as usize;
+            pos += 1;
+            if count > 128 {
+                let value: u8 = *data.get(pos)?;
+                pos += 1;
+                for pixel in line.get_mut(x..x + count - 128)? {
+                    pixel[component] = value;
+                }
+                x += count - 128;
+            } else {
+                if count == 0 {
+                    return None;
+                }
+                for (pixel, &value) in line.get_mut(x..x + count)?.iter_mut().zip(data.get(pos..pos + count)?) {
+                    pixel[component] = value;
+                }
+                pos += count;
+                x += count;
+            }
+        }
+    }
+    Some(pos)
+}
+
+pub(crate) fn from_rgbe(rgbe: [u8; 4]) -> Color3 {
+    if rgbe[3] == 0 {
+        return Color3::new(0., 0., 0.);
+    }
+    let scale: f64 = 2f64.powi(rgbe[3] as i32 - 136);
+    Color3::new(rgbe[0] as f64 + 0.5, rgbe[1] as f64 + 0.5, rgbe[2] as f64 + 0.5) * scale
+}
+
+/// Appends `data` encoded with the Radiance run-length scheme: runs are a count above 128
+/// followed by the repeated byte, literals are a count up to 128 followed by the bytes.
+fn run_length(data: &[u8], out: &mut Vec<u8>) {
+    let run_at = |i: usize| data[i..].iter().take(127).take_while(|&&b| b == data[i]).count();
+
+    let mut i: usize = 0;
+    while i < data.len() {
+        let run: usize = run_at(i);
+        if run >= 3 {
+            out.extend([128 + run as u8, data[i]]);
+            i += run;
+            continue;
+        }
+
+        let start: usize = i;
+        while i < data.len() && i - start < 128 && run_at(i) < 3 {
+            i += 1;
+        }
+        out.push((i - start) as u8);
+        out.extend(&data[start..i]);
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn rgbe_reference() {
+    assert_eq!(to_rgbe(Color3::new(1., 1., 1.)), [128, 128, 128, 129]);
+    assert_eq!(to_rgbe(Color3::new(0.5, 0.25, 0.)), [128, 64, 0, 128]);
+    assert_eq!(to_rgbe(Color3::new(1000., 0., -1.)), [250, 0, 0, 138]);
+    assert_eq!(to_rgbe(Color3::new(0., 0., 0.)), [0; 4]);
+
+    let mut encoded: Vec<u8> = vec![];
+    run_length(&[7, 7, 7, 7, 1, 2, 3, 3], &mut encoded);
+    assert_eq!(encoded, [132, 7, 4, 1, 2, 3, 3]);
+}
+
+#[test]
+fn hdr_round_trip() {
+    for width in [5, 40] {
+        let mut fb = Framebuffer::new(width, 3);
+        for x in 0..width {
+            fb.set(x, 1, Color3::new(x as f64 * 10., 0.5, 0.001));
+            fb.set(x, 2, Color3::new(0.25, 0.25, 0.25));
+        }
+        let mut data: Vec<u8> = vec![];
+        write_hdr(&mut data, &fb).unwrap();
+        let read: Framebuffer = read_hdr(&data).unwrap();
+        assert_eq!((read.width(), read.height()), (width, 3));
+        for (a, b) in read.pixels().iter().zip(fb.pixels()) {
+            assert!((*a - *b).len() <= 0.01 * b.len().max(0.001));
+        }
+    }
+    assert!(read_hdr(b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0").is_err());
+    assert!(read_hdr(b"#?RADIANCE\n\n-Y 4000000000 +X 4000000000\n\x02\x02\x00\x08").is_err());
+}
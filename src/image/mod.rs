@@ -0,0 +1,151 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::error::{Error, Result};
+use crate::loaders::LoadError;
+use crate::utils::linear_to_srgb;
+use crate::vec3::Color3;
+
+mod aov;
+pub use aov::Aovs;
+mod denoise;
+pub use denoise::Denoiser;
+mod exr;
+pub use exr::{write_exr, write_exr_layers, ExrCompression, ExrOptions, ExrPixelType};
+mod hdr;
+pub use hdr::{read_hdr, write_hdr};
+mod png;
+pub use png::{read_png, write_png};
+mod ppm;
+pub use ppm::{read_pfm, read_ppm, write_ppm};
+mod tonemap;
+pub use tonemap::{ToneMap, ToneMapping};
+mod zlib;
+
+/// A rendered image, as linear colors stored row by row from the top left.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color3>,
+}
+
+impl Framebuffer {
+    /// A black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![Color3::default(); width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+    pub fn pixels(&self) -> &[Color3] {
+        &self.pixels
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> Color3 {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+    pub fn set(&mut self, x: u32, y: u32, color: Color3) {
+        self.pixels[y as usize * self.width as usize + x as usize] = color;
+    }
+
+    /// The pixels tone mapped, sRGB-encoded and quantized to 8 bits, as RGB triplets.
+    pub fn to_rgb8(&self, tone: &ToneMapping) -> Vec<u8> {
+        self.pixels.iter().flat_map(|&color| {
+            let c: Color3 = tone.apply(color);
+            [c.x(), c.y(), c.z()].map(|v| (linear_to_srgb(v) * 255.999).floor() as u8)
+        }).collect()
+    }
+}
+
+/// The file formats images can be saved to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Ppm,
+    /// Radiance RGBE, linear.
+    Hdr,
+    /// OpenEXR, linear.
+    Exr,
+}
+
+impl ImageFormat {
+    /// Guesses the format from the extension of `path`.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "hdr" => Some(Self::Hdr),
+            "exr" => Some(Self::Exr),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the image at `path` as linear colors: PNG, binary PPM, PFM or Radiance HDR,
+/// recognized from the start of the file. 8 and 16-bit images are taken as sRGB.
+pub fn load(path: impl AsRef<Path>) -> std::result::Result<Framebuffer, LoadError> {
+    let path: &Path = path.as_ref();
+    let error = |message: String| LoadError::new(path, None, message);
+    let data: Vec<u8> = fs::read(path).map_err(|e| error(e.to_string()))?;
+    let image: io::Result<Framebuffer> = match &data[..data.len().min(2)] {
+        [0x89, b'P'] => read_png(&data),
+        b"P6" => read_ppm(&data),
+        b"PF" | b"Pf" => read_pfm(&data),
+        b"#?" => read_hdr(&data),
+        _ => return Err(error("unsupported image format, expected PNG, PPM, PFM or HDR".to_string())),
+    };
+    image.map_err(|e| error(e.to_string()))
+}
+
+/// Writes `fb` to `path`, in the format given by its extension.
+/// `tone` only applies to the display formats, HDR formats keep the linear radiance.
+/// `exr` only applies to OpenEXR images.
+/// The file is only created once the format is known.
+pub fn save(fb: &Framebuffer, path: impl AsRef<Path>, tone: &ToneMapping, exr: &ExrOptions) -> Result<()> {
+    let path: &Path = path.as_ref();
+    let format: ImageFormat = ImageFormat::from_path(path).ok_or_else(|| Error::invalid_parameter(format!(
+        "unknown image format for {}, expected .png, .ppm, .hdr or .exr",
+        path.display(),
+    )))?;
+
+    write_file(path, |out| match format {
+        ImageFormat::Png => write_png(out, fb, tone),
+        ImageFormat::Ppm => write_ppm(out, fb, tone),
+        ImageFormat::Hdr => write_hdr(out, fb),
+        ImageFormat::Exr => write_exr(out, fb, exr),
+    })
+}
+
+/// Writes `fb` to `path` along with `aovs`: as extra layers for OpenEXR images, as images
+/// named after `path` otherwise (`out.albedo.hdr`, `out.N.hdr`...), which are exact only with `.hdr`.
+pub fn save_layers(fb: &Framebuffer, aovs: &Aovs, path: impl AsRef<Path>, tone: &ToneMapping, exr: &ExrOptions) -> Result<()> {
+    let path: &Path = path.as_ref();
+    if ImageFormat::from_path(path) == Some(ImageFormat::Exr) {
+        return write_file(path, |out| write_exr_layers(out, fb, aovs, exr));
+    }
+
+    save(fb, path, tone, exr)?;
+    let (stem, extension) = (path.file_stem().unwrap_or_default(), path.extension().unwrap_or_default());
+    for (layer, buffer, _) in aovs.layers() {
+        let name: String = format!("{}.{}.{}", stem.to_string_lossy(), layer, extension.to_string_lossy());
+        save(buffer, path.with_file_name(name), &ToneMapping::default(), exr)?;
+    }
+    Ok(())
+}
+
+/// Creates `path` and writes it with `write`, naming the file in errors.
+fn write_file(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>) -> Result<()> {
+    let with_path = |e: io::Error| Error::Io(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+    let mut out = BufWriter::new(File::create(path).map_err(with_path)?);
+    write(&mut out).and_then(|_| out.flush()).map_err(with_path)
+}
@@ -0,0 +1,320 @@
+use std::io::{self, Write};
+
+use crate::utils::srgb_to_linear;
+use crate::vec3::Color3;
+
+use super::{zlib, Framebuffer, ToneMapping};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+/// Writes `fb` as an 8-bit sRGB PNG, tone mapped with `tone`.
+pub fn write_png(out: &mut impl Write, fb: &Framebuffer, tone: &ToneMapping) -> io::Result<()> {
+    let (width, height) = (fb.width() as usize, fb.height() as usize);
+    let rgb: Vec<u8> = fb.to_rgb8(tone);
+
+    // Each row is prefixed with the filter that suits it best.
+    let stride: usize = 3 * width;
+    let mut filtered: Vec<u8> = Vec::with_capacity((stride + 1) * height);
+    let mut candidate: Vec<u8> = vec![0; stride];
+    let zero_row: Vec<u8> = vec![0; stride];
+    for y in 0..height {
+        let row: &[u8] = &rgb[y * stride..(y + 1) * stride];
+        let prev: &[u8] = if y == 0 { &zero_row } else { &rgb[(y - 1) * stride..y * stride] };
+
+        let mut best: (u8, u64, Vec<u8>) = (0, u64::MAX, vec![]);
+        for filter in 0..5u8 {
+            apply_filter(filter, row, prev, &mut candidate);
+            // Smallest sum of absolute values, taking bytes as signed.
+            let score: u64 = candidate.iter().map(|&b| (b as i8).unsigned_abs() as u64).sum();
+            if score < best.1 {
+                best = (filter, score, candidate.clone());
+            }
+        }
+        filtered.push(best.0);
+        filtered.extend(best.2);
+    }
+
+    let mut ihdr: Vec<u8> = vec![];
+    ihdr.extend((width as u32).to_be_bytes());
+    ihdr.extend((height as u32).to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlacing.
+    ihdr.extend([8, 2, 0, 0, 0]);
+
+    out.write_all(&SIGNATURE)?;
+    write_chunk(out, b"IHDR", &ihdr)?;
+    write_chunk(out, b"IDAT", &zlib::compress(&filtered))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+/// Filters `row` into `out` with one of the five PNG filter types, `prev` being the row above.
+fn apply_filter(filter: u8, row: &[u8], prev: &[u8], out: &mut [u8]) {
+    const BPP: usize = 3;
+    for i in 0..row.len() {
+        let a: u8 = if i >= BPP { row[i - BPP] } else { 0 };
+        let b: u8 = prev[i];
+        let c: u8 = if i >= BPP { prev[i - BPP] } else { 0 };
+        let predictor: u8 = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            _ => paeth(a, b, c),
+        };
+        out[i] = row[i].wrapping_sub(predictor);
+    }
+}
+
+pub(crate) fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p: i16 = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+}
+
+/// Starting column and row, then spacing, of the 7 passes of Adam7 interlacing.
+const ADAM7: [(usize, usize, usize, usize); 7] = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+
+/// Reads a PNG of any color type and bit depth, interlaced or not, decoding its sRGB values to linear.
+/// Transparency is ignored.
+pub fn read_png(data: &[u8]) -> io::Result<Framebuffer> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if data.get(..8) != Some(&SIGNATURE[..]) {
+        return Err(invalid("not a PNG file"));
+    }
+
+    let mut header: Option<Header> = None;
+    let mut palette: Vec<Color3> = vec![];
+    let mut compressed: Vec<u8> = vec![];
+    let mut pos: usize = 8;
+    loop {
+        let length: usize = data.get(pos..pos + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize).ok_or_else(|| invalid("truncated file"))?;
+        let chunk: &[u8] = data.get(pos + 4..pos + 8 + length).ok_or_else(|| invalid("truncated file"))?;
+        let crc: &[u8] = data.get(pos + 8 + length..pos + 12 + length).ok_or_else(|| invalid("truncated file"))?;
+        if crc32(chunk).to_be_bytes() != crc {
+            return Err(invalid("chunk checksum mismatch"));
+        }
+        let (kind, body) = chunk.split_at(4);
+        match kind {
+            b"IHDR" => header = Some(Header::parse(body).ok_or_else(|| invalid("invalid header"))?),
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| Color3::new(c[0] as f64, c[1] as f64, c[2] as f64) / 255.).collect(),
+            b"IDAT" => compressed.extend_from_slice(body),
+            b"IEND" => break,
+            // Ancillary chunks can be skipped, the others are needed to read the image.
+            _ if kind[0].is_ascii_uppercase() => return Err(invalid(&format!("unsupported chunk {}", String::from_utf8_lossy(kind)))),
+            _ => {}
+        }
+        pos += 12 + length;
+    }
+
+    let header: Header = header.ok_or_else(|| invalid("missing header"))?;
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(invalid("missing palette"));
+    }
+
+    let (width, height) = (header.width as usize, header.height as usize);
+    let channels: usize = header.channels();
+    let bits_per_pixel: usize = channels * header.bit_depth as usize;
+    let max: f64 = ((1u32 << header.bit_depth) - 1) as f64;
+    let passes: &[(usize, usize, usize, usize)] = if header.interlaced { &ADAM7 } else { &[(0, 0, 1, 1)] };
+
+    // The size is only trusted once the image data is known to cover it.
+    let expected: Option<usize> = passes.iter().try_fold(0usize, |sum, &(x0, y0, dx, dy)| {
+        if x0 >= width || y0 >= height { return Some(sum) }
+        let (pass_width, pass_height) = ((width - x0).div_ceil(dx), (height - y0).div_ceil(dy));
+        let stride: usize = pass_width.checked_mul(bits_per_pixel)?.div_ceil(8);
+        sum.checked_add(stride.checked_add(1)?.checked_mul(pass_height)?)
+    });
+    let raw: Vec<u8> = zlib::decompress(&compressed)?;
+    if expected.is_none_or(|expected| raw.len() < expected) {
+        return Err(invalid("truncated image data"));
+    }
+    let mut fb = Framebuffer::new(header.width, header.height);
+
+    let mut rest: &[u8] = &raw;
+    for &(x0, y0, dx, dy) in passes {
+        let (pass_width, pass_height) = ((width + dx - 1 - x0) / dx, (height + dy - 1 - y0) / dy);
+        if x0 >= width || y0 >= height || pass_width == 0 || pass_height == 0 {
+            continue;
+        }
+        let stride: usize = (pass_width * bits_per_pixel).div_ceil(8);
+        let mut prev: Vec<u8> = vec![0; stride];
+        let mut row: Vec<u8> = vec![0; stride];
+        for j in 0..pass_height {
+            let (line, tail) = rest.split_at_checked(stride + 1).ok_or_else(|| invalid("truncated image data"))?;
+            rest = tail;
+            row.copy_from_slice(&line[1..]);
+            unfilter(line[0], &mut row, &prev, bits_per_pixel.div_ceil(8)).ok_or_else(|| invalid("invalid filter type"))?;
+
+            for i in 0..pass_width {
+                let sample = |c: usize| -> u32 {
+                    let bit: usize = (i * channels + c) * header.bit_depth as usize;
+                    match header.bit_depth {
+                        16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]) as u32,
+                        8 => row[bit / 8] as u32,
+                        depth => (row[bit / 8] >> (8 - depth as usize - bit % 8)) as u32 & ((1 << depth) - 1),
+                    }
+                };
+                let encoded: Color3 = match header.color_type {
+                    0 | 4 => { let g: f64 = sample(0) as f64 / max; Color3::new(g, g, g) }
+                    3 => *palette.get(sample(0) as usize).ok_or_else(|| invalid("palette index out of range"))?,
+                    _ => Color3::new(sample(0) as f64, sample(1) as f64, sample(2) as f64) / max,
+                };
+                let color = Color3::new(srgb_to_linear(encoded.x()), srgb_to_linear(encoded.y()), srgb_to_linear(encoded.z()));
+                fb.set((x0 + i * dx) as u32, (y0 + j * dy) as u32, color);
+            }
+            std::mem::swap(&mut prev, &mut row);
+        }
+    }
+    Ok(fb)
+}
+
+/// The IHDR chunk.
+struct Header {
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn parse(body: &[u8]) -> Option<Self> {
+        let body: &[u8; 13] = body.try_into().ok()?;
+        let header = Self {
+            width: u32::from_be_bytes([body[0], body[1], body[2], body[3]]),
+            height: u32::from_be_bytes([body[4], body[5], body[6], body[7]]),
+            bit_depth: body[8],
+            color_type: body[9],
+            interlaced: body[12] == 1,
+        };
+        let depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => return None,
+        };
+        let valid: bool = depths.contains(&header.bit_depth) && body[10] == 0 && body[11] == 0 && body[12] <= 1;
+        valid.then_some(header)
+    }
+
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// Reverses the filter of `row` in place, `prev` being the row above once unfiltered
+/// and `bpp` the number of bytes per pixel, at least 1.
+fn unfilter(filter: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> Option<()> {
+    for i in 0..row.len() {
+        let a: u8 = if i >= bpp { row[i - bpp] } else { 0 };
+        let b: u8 = prev[i];
+        let c: u8 = if i >= bpp { prev[i - bpp] } else { 0 };
+        let predictor: u8 = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => return None,
+        };
+        row[i] = row[i].wrapping_add(predictor);
+    }
+    Some(())
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc: u32 = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut n: usize = 0;
+    while n < 256 {
+        let mut c: u32 = n as u32;
+        let mut k: usize = 0;
+        while k < 8 {
+            c = if c & 1 != 0 { 0xEDB8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+pub(crate) fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    !bytes.into_iter().fold(!0u32, |c, &b| CRC_TABLE[((c ^ b as u32) & 0xFF) as usize] ^ (c >> 8))
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn crc32_reference() {
+    assert!(crc32(b"IEND") == 0xAE42_6082);
+}
+
+#[test]
+fn png_layout() {
+    let mut fb = Framebuffer::new(64, 32);
+    for y in 0..32 {
+        for x in 0..64 {
+            fb.set(x, y, Color3::new(0.5, 0.5, 0.5));
+        }
+    }
+    let mut data: Vec<u8> = vec![];
+    write_png(&mut data, &fb, &ToneMapping::default()).unwrap();
+
+    // Chunks are a size, a type, the data and a checksum of the type and data.
+    assert_eq!(data[..8], SIGNATURE);
+    assert_eq!(data[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+    assert_eq!(data[16..29], [0, 0, 0, 64, 0, 0, 0, 32, 8, 2, 0, 0, 0]);
+    assert_eq!(data[29..33], crc32(&data[12..29]).to_be_bytes());
+    let size: usize = u32::from_be_bytes(data[33..37].try_into().unwrap()) as usize;
+    assert_eq!(data[37..41], *b"IDAT");
+    assert_eq!(data[41 + size..45 + size], crc32(&data[37..41 + size]).to_be_bytes());
+    assert_eq!(data[45 + size..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xAE, 0x42, 0x60, 0x82]);
+
+    // Rows below the first repeat the row above, which the Up filter turns to zeros, and deflate to almost nothing from 6 KB.
+    let filtered: Vec<u8> = zlib::decompress(&data[41..41 + size]).unwrap();
+    assert_eq!(filtered.len(), 32 * (1 + 3 * 64));
+    for row in filtered.chunks(1 + 3 * 64).skip(1) {
+        assert!(row[0] == 2 && row[1..].iter().all(|&b| b == 0));
+    }
+    assert!(size < 100);
+}
+#[test]
+fn png_round_trip() {
+    fastrand::seed(11);
+    let mut fb = Framebuffer::new(13, 7);
+    for y in 0..7 {
+        for x in 0..13 {
+            fb.set(x, y, Color3::new(fastrand::f64(), fastrand::f64(), fastrand::f64()));
+        }
+    }
+    let mut data: Vec<u8> = vec![];
+    write_png(&mut data, &fb, &ToneMapping::default()).unwrap();
+    let read: Framebuffer = read_png(&data).unwrap();
+    for (a, b) in read.pixels().iter().zip(fb.pixels()) {
+        assert!((*a - *b).len() < 0.02);
+    }
+
+    data[20] ^= 1;
+    assert_eq!(read_png(&data).unwrap_err().to_string(), "chunk checksum mismatch");
+
+    // A huge size in the header, with a valid checksum, isn't allocated.
+    data[20] ^= 1;
+    data[16..24].copy_from_slice(&[0xFF; 8]);
+    let crc: u32 = crc32(&data[12..29]);
+    data[29..33].copy_from_slice(&crc.to_be_bytes());
+    assert_eq!(read_png(&data).unwrap_err().to_string(), "truncated image data");
+}
@@ -0,0 +1,146 @@
+use std::io::{self, Write};
+
+use crate::utils::srgb_to_linear;
+use crate::vec3::Color3;
+
+use super::{Framebuffer, ToneMapping};
+
+/// Writes `fb` as a binary (P6) PPM, with 8 bits per channel, tone mapped with `tone`.
+pub fn write_ppm(out: &mut impl Write, fb: &Framebuffer, tone: &ToneMapping) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", fb.width(), fb.height())?;
+    out.write_all(&fb.to_rgb8(tone))
+}
+
+/// Reads a binary (P6) PPM of 8 or 16 bits per channel, decoding its sRGB values to linear.
+pub fn read_ppm(data: &[u8]) -> io::Result<Framebuffer> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    let (fields, pos) = header(data).ok_or_else(|| invalid("truncated header"))?;
+    if fields[0] != b"P6" {
+        return Err(invalid("not a binary PPM (P6)"));
+    }
+    let number = |field: &[u8]| std::str::from_utf8(field).ok().and_then(|s| s.parse::<u32>().ok()).ok_or_else(|| invalid("invalid header"));
+    let (width, height, max) = (number(fields[1])?, number(fields[2])?, number(fields[3])?);
+    if max == 0 || max > 65535 {
+        return Err(invalid("maximum value must be between 1 and 65535"));
+    }
+    let samples: &[u8] = &data[pos..];
+
+    let bytes: usize = if max < 256 { 1 } else { 2 };
+    let pixels: usize = (width as usize).checked_mul(height as usize).ok_or_else(|| invalid("image too large"))?;
+    let count: usize = pixels.checked_mul(3 * bytes).ok_or_else(|| invalid("image too large"))?;
+    if samples.len() < count {
+        return Err(invalid("truncated pixel data"));
+    }
+    let sample = |i: usize| -> f64 {
+        let v: u32 = if bytes == 1 { samples[i] as u32 } else { u16::from_be_bytes([samples[2 * i], samples[2 * i + 1]]) as u32 };
+        srgb_to_linear(v as f64 / max as f64)
+    };
+
+    let mut fb = Framebuffer::new(width, height);
+    for i in 0..pixels {
+        fb.pixels[i] = Color3::new(sample(3 * i), sample(3 * i + 1), sample(3 * i + 2));
+    }
+    Ok(fb)
+}
+
+/// Reads a PFM image, color or grayscale, which holds linear floats.
+pub fn read_pfm(data: &[u8]) -> io::Result<Framebuffer> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let (fields, pos) = header(data).ok_or_else(|| invalid("truncated header"))?;
+    let channels: usize = match fields[0] {
+        b"PF" => 3,
+        b"Pf" => 1,
+        _ => return Err(invalid("not a PFM file")),
+    };
+    let text = |field: &[u8]| std::str::from_utf8(field).ok().map(str::to_string).ok_or_else(|| invalid("invalid header"));
+    let (width, height, scale) = (text(fields[1])?.parse::<u32>(), text(fields[2])?.parse::<u32>(), text(fields[3])?.parse::<f64>());
+    let (Ok(width), Ok(height), Ok(scale)) = (width, height, scale) else { return Err(invalid("invalid header")) };
+    // The sign of the scale gives the byte order.
+    let little_endian: bool = scale < 0.;
+
+    let samples: &[u8] = &data[pos..];
+    let size: usize = (width as usize).checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(channels * 4))
+        .ok_or_else(|| invalid("image too large"))?;
+    if samples.len() < size {
+        return Err(invalid("truncated pixel data"));
+    }
+    let sample = |i: usize| -> f64 {
+        let bytes: [u8; 4] = [samples[4 * i], samples[4 * i + 1], samples[4 * i + 2], samples[4 * i + 3]];
+        (if little_endian { f32::from_le_bytes(bytes) } else { f32::from_be_bytes(bytes) }) as f64
+    };
+
+    // Rows go up from the bottom.
+    let mut fb = Framebuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let i: usize = ((height - 1 - y) as usize * width as usize + x as usize) * channels;
+            let color: Color3 = match channels {
+                3 => Color3::new(sample(i), sample(i + 1), sample(i + 2)),
+                _ => Color3::new(sample(i), sample(i), sample(i)),
+            };
+            fb.set(x, y, color);
+        }
+    }
+    Ok(fb)
+}
+
+/// Splits the 4 fields of a Netpbm header (magic, width, height, maximum or scale), separated by
+/// whitespace or comments. Also returns where the data starts, after the single whitespace ending the header.
+fn header(data: &[u8]) -> Option<(Vec<&[u8]>, usize)> {
+    let mut fields: Vec<&[u8]> = vec![];
+    let mut pos: usize = 0;
+    while fields.len() < 4 {
+        match data.get(pos)? {
+            b'#' => while pos < data.len() && data[pos] != b'\n' { pos += 1 },
+            c if c.is_ascii_whitespace() => pos += 1,
+            _ => {
+                let start: usize = pos;
+                while pos < data.len() && !data[pos].is_ascii_whitespace() { pos += 1 }
+                fields.push(&data[start..pos]);
+            }
+        }
+    }
+    (pos < data.len()).then_some((fields, pos + 1))
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn ppm_round_trip() {
+    let mut fb = Framebuffer::new(3, 2);
+    fb.set(0, 0, Color3::new(1., 0., 0.));
+    fb.set(2, 1, Color3::new(0.2, 0.5, 0.));
+    let mut data: Vec<u8> = vec![];
+    write_ppm(&mut data, &fb, &ToneMapping::default()).unwrap();
+    // With a comment, which writers may add
+    data.splice(3..3, b"# rustracer\n".iter().copied());
+
+    let read: Framebuffer = read_ppm(&data).unwrap();
+    assert_eq!((read.width(), read.height()), (3, 2));
+    for (a, b) in read.pixels().iter().zip(fb.pixels()) {
+        assert!((*a - *b).len() < 0.01);
+    }
+    assert!(read_ppm(b"P3\n1 1\n255\n0 0 0").is_err());
+    assert!(read_ppm(b"P6\n2 2\n255\n\0\0\0").is_err());
+    assert_eq!(read_ppm(b"P6\n4294967295 4294967295\n255\n\0\0\0").unwrap_err().to_string(), "image too large");
+    assert_eq!(read_ppm(b"P6\n65536 65536\n65535\n\0\0\0").unwrap_err().to_string(), "truncated pixel data");
+}
+
+#[test]
+fn pfm_orientation() {
+    // 2x1 color little endian, then 1x2 grayscale big endian going up
+    let mut data: Vec<u8> = b"PF\n2 1\n-1.0\n".to_vec();
+    data.extend([1f32, 2., 3., 4., 5., 6.].iter().flat_map(|v| v.to_le_bytes()));
+    let fb: Framebuffer = read_pfm(&data).unwrap();
+    assert_eq!(fb.get(1, 0), Color3::new(4., 5., 6.));
+
+    let mut data: Vec<u8> = b"Pf\n1 2\n1.0\n".to_vec();
+    data.extend([0.25f32, 0.75].iter().flat_map(|v| v.to_be_bytes()));
+    let fb: Framebuffer = read_pfm(&data).unwrap();
+    assert_eq!((fb.get(0, 0), fb.get(0, 1)), (Color3::new(0.75, 0.75, 0.75), Color3::new(0.25, 0.25, 0.25)));
+    assert_eq!(read_pfm(b"PF\n4294967295 4294967295\n-1.0\n\0\0\0\0").unwrap_err().to_string(), "image too large");
+}
@@ -0,0 +1,129 @@
+use crate::vec3::{Color3, Vec3};
+
+/// Curves compressing linear radiance into the displayable [0; 1] range.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ToneMap {
+    /// Values above 1 are clipped.
+    Clamp,
+    /// `L / (1 + L)` on the luminance, which never reaches white.
+    Reinhard,
+    /// Reinhard with a luminance mapped to pure white.
+    ReinhardExtended { white: f64 },
+    /// John Hable's filmic curve from Uncharted 2.
+    Hable,
+    /// Stephen Hill's fit of the ACES reference and sRGB output transforms.
+    Aces,
+}
+
+/// How a rendered image is turned into display colors: an exposure and a tone curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ToneMapping {
+    /// Exposure adjustment in stops: each unit doubles the brightness.
+    pub exposure: f64,
+    pub operator: ToneMap,
+}
+
+impl Default for ToneMapping {
+    fn default() -> Self {
+        Self {
+            exposure: 0.,
+            operator: ToneMap::Clamp,
+        }
+    }
+}
+
+impl ToneMapping {
+    pub fn new(exposure: f64, operator: ToneMap) -> Self {
+        Self {
+            exposure,
+            operator,
+        }
+    }
+
+    /// Maps a linear color to linear display values in [0; 1], before any transfer function.
+    pub fn apply(&self, color: Color3) -> Color3 {
+        let c: Color3 = color * 2f64.powf(self.exposure);
+        let mut mapped: Color3 = match self.operator {
+            ToneMap::Clamp => c,
+            ToneMap::Reinhard => scale_luminance(c, |l| l / (1. + l)),
+            ToneMap::ReinhardExtended { white } => scale_luminance(c, |l| l * (1. + l / (white * white)) / (1. + l)),
+            ToneMap::Hable => {
+                // Exposure bias and linear white point of the original operator.
+                const WHITE: f64 = 11.2;
+                let w: f64 = hable(WHITE);
+                Color3::new(hable(2. * c.x()) / w, hable(2. * c.y()) / w, hable(2. * c.z()) / w)
+            }
+            ToneMap::Aces => aces_fitted(c),
+        };
+        mapped.clamp(0., 1.);
+        mapped
+    }
+}
+
+/// Rescales `c` so that its luminance becomes `f(luminance)`, which keeps its hue.
+fn scale_luminance(c: Color3, f: impl Fn(f64) -> f64) -> Color3 {
+    let l: f64 = c.luminance();
+    if l <= 0. { Color3::default() } else { c * (f(l) / l) }
+}
+
+fn hable(x: f64) -> f64 {
+    const A: f64 = 0.15; // Shoulder strength
+    const B: f64 = 0.50; // Linear strength
+    const C: f64 = 0.10; // Linear angle
+    const D: f64 = 0.20; // Toe strength
+    const E: f64 = 0.02; // Toe numerator
+    const F: f64 = 0.30; // Toe denominator
+    (x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F) - E / F
+}
+
+fn aces_fitted(c: Color3) -> Color3 {
+    // sRGB to the ACES reference transform input space, and back.
+    const INPUT: [[f64; 3]; 3] = [
+        [0.59719, 0.35458, 0.04823],
+        [0.07600, 0.90834, 0.01566],
+        [0.02840, 0.13383, 0.83777],
+    ];
+    const OUTPUT: [[f64; 3]; 3] = [
+        [1.60475, -0.53108, -0.07367],
+        [-0.10208, 1.10813, -0.00605],
+        [-0.00327, -0.07276, 1.07602],
+    ];
+    let mul = |m: &[[f64; 3]; 3], v: Vec3| Vec3::new(
+        m[0][0] * v.x() + m[0][1] * v.y() + m[0][2] * v.z(),
+        m[1][0] * v.x() + m[1][1] * v.y() + m[1][2] * v.z(),
+        m[2][0] * v.x() + m[2][1] * v.y() + m[2][2] * v.z(),
+    );
+    // Fit of the reference rendering and output device transforms.
+    let rrt_odt = |x: f64| (x * (x + 0.0245786) - 0.000090537) / (x * (0.983729 * x + 0.4329510) + 0.238081);
+
+    let v: Vec3 = mul(&INPUT, c);
+    mul(&OUTPUT, Vec3::new(rrt_odt(v.x()), rrt_odt(v.y()), rrt_odt(v.z())))
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn tone_curves() {
+    let gray = |v: f64| Color3::new(v, v, v);
+    let operators: [ToneMap; 5] = [ToneMap::Clamp, ToneMap::Reinhard, ToneMap::ReinhardExtended { white: 4. }, ToneMap::Hable, ToneMap::Aces];
+
+    for operator in operators {
+        let tone = ToneMapping::new(0., operator);
+        assert_eq!(tone.apply(gray(0.)).x(), 0.);
+        // Monotonic, and within the display range.
+        let mut prev: f64 = 0.;
+        for i in 1..200 {
+            let v: f64 = tone.apply(gray(i as f64 * 0.1)).x();
+            assert!(v >= prev && v <= 1., "{:?} at {}", operator, i);
+            prev = v;
+        }
+    }
+
+    assert!((ToneMapping::new(0., ToneMap::Reinhard).apply(gray(1.)).x() - 0.5).abs() < 1e-12);
+    assert!((ToneMapping::new(0., ToneMap::ReinhardExtended { white: 4. }).apply(gray(4.)).x() - 1.).abs() < 1e-12);
+    assert!((ToneMapping::new(0., ToneMap::Hable).apply(gray(5.6)).x() - 1.).abs() < 1e-12);
+    // One stop up doubles the linear values.
+    assert_eq!(ToneMapping::new(1., ToneMap::Clamp).apply(gray(0.25)), gray(0.5));
+}
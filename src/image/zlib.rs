@@ -0,0 +1,635 @@
+//! Zlib streams (RFC 1950) holding DEFLATE data (RFC 1951), as used by PNG and OpenEXR.
+
+use std::io;
+
+/// Longest distance a match can refer back to.
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried when looking for a match.
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: usize = 15;
+/// Symbols per block, after which a new set of Huffman codes is computed.
+const BLOCK_SYMBOLS: usize = 1 << 16;
+/// Most bytes a stored block can hold.
+const STORED_SIZE: usize = 65535;
+
+const LENGTH_BASE: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+const DIST_BASE: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DIST_EXTRA: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+/// Order in which the lengths of the code length code are stored.
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// Compresses `data` into a zlib stream.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter::new();
+    // Deflate with a 32K window, default compression level.
+    out.bytes.extend([0x78, 0x9C]);
+
+    let symbols: Vec<Symbol> = lz77(data);
+    let mut blocks = symbols.chunks(BLOCK_SYMBOLS).peekable();
+    if blocks.peek().is_none() {
+        write_block(&mut out, &[], true);
+    }
+    let mut start: usize = 0;
+    while let Some(block) = blocks.next() {
+        let last: bool = blocks.peek().is_none();
+        let end: usize = start + block.iter().map(|symbol| match *symbol {
+            Symbol::Literal(_) => 1,
+            Symbol::Match { length, .. } => length as usize,
+        }).sum::<usize>();
+
+        // Data that doesn't compress is stored as is instead.
+        let mark: usize = out.bits();
+        write_block(&mut out, block, last);
+        // Each stored block takes 5 bytes over its data, at most, after padding to a byte.
+        let stored_bits: usize = 7 + 8 * (end - start + 5 * (end - start).div_ceil(STORED_SIZE));
+        if out.bits() - mark > stored_bits {
+            out.rewind(mark);
+            write_stored(&mut out, &data[start..end], last);
+        }
+        start = end;
+    }
+
+    let mut bytes: Vec<u8> = out.finish();
+    bytes.extend(adler32(data).to_be_bytes());
+    bytes
+}
+
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= 65521;
+        b %= 65521;
+    }
+    (b << 16) | a
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Symbol {
+    Literal(u8),
+    /// Copy of `length` bytes starting `distance` bytes back.
+    Match { length: u16, distance: u16 },
+}
+
+/// Greedy LZ77 parsing, with hash chains over 3-byte sequences.
+fn lz77(data: &[u8]) -> Vec<Symbol> {
+    let hash = |i: usize| -> usize {
+        let v: u32 = (data[i] as u32) << 16 | (data[i + 1] as u32) << 8 | data[i + 2] as u32;
+        (v.wrapping_mul(0x9E37_79B1) >> (32 - HASH_BITS)) as usize
+    };
+    let mut head: Vec<usize> = vec![usize::MAX; 1 << HASH_BITS];
+    let mut prev: Vec<usize> = vec![usize::MAX; WINDOW_SIZE];
+    let insert = |i: usize, head: &mut Vec<usize>, prev: &mut Vec<usize>| {
+        if i + MIN_MATCH <= data.len() {
+            let h: usize = hash(i);
+            prev[i % WINDOW_SIZE] = head[h];
+            head[h] = i;
+        }
+    };
+
+    let mut symbols: Vec<Symbol> = Vec::with_capacity(data.len() / 2);
+    let mut i: usize = 0;
+    while i < data.len() {
+        let (mut best_len, mut best_dist) = (0usize, 0usize);
+        if i + MIN_MATCH <= data.len() {
+            let max_len: usize = MAX_MATCH.min(data.len() - i);
+            let mut candidate: usize = head[hash(i)];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || i - candidate > WINDOW_SIZE - 1 {
+                    break
+                }
+                let len: usize = data[candidate..].iter().zip(&data[i..i + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, i - candidate);
+                    if len == max_len { break }
+                }
+                let next: usize = prev[candidate % WINDOW_SIZE];
+                if next == usize::MAX || next >= candidate { break }
+                candidate = next;
+            }
+        }
+
+        if best_len >= MIN_MATCH {
+            symbols.push(Symbol::Match { length: best_len as u16, distance: best_dist as u16 });
+            for k in i..i + best_len {
+                insert(k, &mut head, &mut prev);
+            }
+            i += best_len;
+        } else {
+            symbols.push(Symbol::Literal(data[i]));
+            insert(i, &mut head, &mut prev);
+            i += 1;
+        }
+    }
+    symbols
+}
+
+/// Index of the length or distance code covering `value`, among codes starting at `bases`.
+fn code_index(bases: &[u16], value: u16) -> usize {
+    bases.iter().rposition(|&base| base <= value).unwrap_or(0)
+}
+
+/// Writes `symbols` as a block compressed with its own Huffman codes.
+fn write_block(out: &mut BitWriter, symbols: &[Symbol], last: bool) {
+    let mut lit_freq = [0u32; 286];
+    let mut dist_freq = [0u32; 30];
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Literal(byte) => lit_freq[byte as usize] += 1,
+            Symbol::Match { length, distance } => {
+                lit_freq[257 + code_index(&LENGTH_BASE, length)] += 1;
+                dist_freq[code_index(&DIST_BASE, distance)] += 1;
+            }
+        }
+    }
+    lit_freq[256] += 1;
+
+    let lit_lengths: Vec<u8> = huffman_lengths(&lit_freq, 15);
+    let dist_lengths: Vec<u8> = huffman_lengths(&dist_freq, 15);
+    let hlit: usize = 257.max(lit_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+    let hdist: usize = 1.max(dist_lengths.iter().rposition(|&l| l > 0).unwrap_or(0) + 1);
+
+    // Run-length encode both sets of lengths together, with the code length alphabet.
+    let all_lengths: Vec<u8> = lit_lengths[..hlit].iter().chain(&dist_lengths[..hdist]).copied().collect();
+    let runs: Vec<(u8, u8)> = run_length(&all_lengths);
+    let mut cl_freq = [0u32; 19];
+    for &(code, _) in runs.iter() {
+        cl_freq[code as usize] += 1;
+    }
+    let cl_lengths: Vec<u8> = huffman_lengths(&cl_freq, 7);
+    let hclen: usize = 4.max(CODE_LENGTH_ORDER.iter().rposition(|&i| cl_lengths[i] > 0).unwrap_or(0) + 1);
+
+    out.write(last as u32, 1);
+    out.write(2, 2);
+    out.write((hlit - 257) as u32, 5);
+    out.write((hdist - 1) as u32, 5);
+    out.write((hclen - 4) as u32, 4);
+    for &i in CODE_LENGTH_ORDER[..hclen].iter() {
+        out.write(cl_lengths[i] as u32, 3);
+    }
+
+    let cl_codes: Vec<u32> = canonical_codes(&cl_lengths);
+    for &(code, extra) in runs.iter() {
+        out.write_code(cl_codes[code as usize], cl_lengths[code as usize]);
+        match code {
+            16 => out.write(extra as u32, 2),
+            17 => out.write(extra as u32, 3),
+            18 => out.write(extra as u32, 7),
+            _ => {}
+        }
+    }
+
+    let lit_codes: Vec<u32> = canonical_codes(&lit_lengths);
+    let dist_codes: Vec<u32> = canonical_codes(&dist_lengths);
+    for symbol in symbols {
+        match *symbol {
+            Symbol::Literal(byte) => out.write_code(lit_codes[byte as usize], lit_lengths[byte as usize]),
+            Symbol::Match { length, distance } => {
+                let l: usize = code_index(&LENGTH_BASE, length);
+                out.write_code(lit_codes[257 + l], lit_lengths[257 + l]);
+                out.write((length - LENGTH_BASE[l]) as u32, LENGTH_EXTRA[l]);
+
+                let d: usize = code_index(&DIST_BASE, distance);
+                out.write_code(dist_codes[d], dist_lengths[d]);
+                out.write((distance - DIST_BASE[d]) as u32, DIST_EXTRA[d]);
+            }
+        }
+    }
+    out.write_code(lit_codes[256], lit_lengths[256]);
+}
+
+/// Writes `data` as stored blocks, uncompressed.
+fn write_stored(out: &mut BitWriter, data: &[u8], last: bool) {
+    let count: usize = data.len().div_ceil(STORED_SIZE);
+    for (i, chunk) in data.chunks(STORED_SIZE).enumerate() {
+        out.write((last && i + 1 == count) as u32, 1);
+        out.write(0, 2);
+        out.align();
+        let len: u16 = chunk.len() as u16;
+        out.bytes.extend(len.to_le_bytes());
+        out.bytes.extend((!len).to_le_bytes());
+        out.bytes.extend(chunk);
+    }
+}
+
+/// Encodes code lengths as (symbol, extra bits) pairs of the code length alphabet:
+/// 16 repeats the previous length, 17 and 18 are runs of zeros.
+fn run_length(lengths: &[u8]) -> Vec<(u8, u8)> {
+    let mut runs: Vec<(u8, u8)> = vec![];
+    let mut i: usize = 0;
+    while i < lengths.len() {
+        let len: u8 = lengths[i];
+        let run: usize = lengths[i..].iter().take_while(|&&l| l == len).count();
+
+        if len == 0 && run >= 11 {
+            let n: usize = run.min(138);
+            runs.push((18, (n - 11) as u8));
+            i += n;
+        } else if len == 0 && run >= 3 {
+            runs.push((17, (run - 3) as u8));
+            i += run;
+        } else if len != 0 && run >= 4 {
+            runs.push((len, 0));
+            let n: usize = (run - 1).min(6);
+            runs.push((16, (n - 3) as u8));
+            i += 1 + n;
+        } else {
+            runs.push((len, 0));
+            i += 1;
+        }
+    }
+    runs
+}
+
+/// Computes Huffman code lengths for the symbol frequencies `freq`, no longer than `max_bits`.
+/// At least two symbols get a code, so that the code is always complete.
+fn huffman_lengths(freq: &[u32], max_bits: u8) -> Vec<u8> {
+    let mut freq: Vec<u32> = freq.to_vec();
+    for _ in freq.iter().filter(|&&f| f > 0).count()..2 {
+        if let Some(f) = freq.iter_mut().find(|f| **f == 0) {
+            *f = 1;
+        }
+    }
+
+    loop {
+        let lengths: Vec<u8> = huffman_tree_depths(&freq);
+        if lengths.iter().all(|&l| l <= max_bits) {
+            return lengths
+        }
+        // Flatten the distribution until the tree is shallow enough.
+        for f in freq.iter_mut().filter(|f| **f > 0) {
+            *f = (*f).div_ceil(2);
+        }
+    }
+}
+
+/// Depth of each symbol in a Huffman tree built over `freq`. Symbols of frequency 0 get depth 0.
+fn huffman_tree_depths(freq: &[u32]) -> Vec<u8> {
+    // Nodes: leaves first, then internal nodes as they are created.
+    let mut weight: Vec<u64> = vec![];
+    let mut parent: Vec<usize> = vec![];
+    let mut queue: Vec<usize> = vec![];
+    let mut leaves: Vec<(usize, usize)> = vec![];
+    for (symbol, &f) in freq.iter().enumerate() {
+        if f > 0 {
+            leaves.push((symbol, weight.len()));
+            queue.push(weight.len());
+            weight.push(f as u64);
+            parent.push(usize::MAX);
+        }
+    }
+
+    // Simple selection of the two lightest nodes: alphabets are at most 286 symbols.
+    while queue.len() > 1 {
+        queue.sort_by_key(|&n| std::cmp::Reverse(weight[n]));
+        let (a, b) = (queue.pop().unwrap(), queue.pop().unwrap());
+        let node: usize = weight.len();
+        weight.push(weight[a] + weight[b]);
+        parent.push(usize::MAX);
+        parent[a] = node;
+        parent[b] = node;
+        queue.push(node);
+    }
+
+    let mut lengths: Vec<u8> = vec![0; freq.len()];
+    for (symbol, node) in leaves {
+        let (mut depth, mut n) = (0u8, node);
+        while parent[n] != usize::MAX {
+            depth += 1;
+            n = parent[n];
+        }
+        lengths[symbol] = depth;
+    }
+    lengths
+}
+
+/// Canonical Huffman codes for the code lengths `lengths`, as defined by DEFLATE.
+pub(crate) fn canonical_codes(lengths: &[u8]) -> Vec<u32> {
+    let mut count = [0u32; 16];
+    for &l in lengths {
+        count[l as usize] += 1;
+    }
+    count[0] = 0;
+
+    let mut next = [0u32; 16];
+    let mut code: u32 = 0;
+    for bits in 1..16 {
+        code = (code + count[bits - 1]) << 1;
+        next[bits] = code;
+    }
+
+    lengths.iter().map(|&l| {
+        if l == 0 { return 0 }
+        let c: u32 = next[l as usize];
+        next[l as usize] += 1;
+        c
+    }).collect()
+}
+
+
+/// Decompresses a zlib stream, checking its Adler-32 checksum.
+pub fn decompress(data: &[u8]) -> io::Result<Vec<u8>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid zlib stream: {}", message));
+    if data.len() < 6 || data[0] & 0x0F != 8 || !u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31) {
+        return Err(invalid("bad header"));
+    }
+    if data[1] & 0x20 != 0 {
+        return Err(invalid("preset dictionaries are not supported"));
+    }
+
+    let mut input = BitReader::new(&data[2..]);
+    let mut out: Vec<u8> = vec![];
+    loop {
+        let last: bool = input.read(1).ok_or_else(|| invalid("truncated"))? == 1;
+        match input.read(2).ok_or_else(|| invalid("truncated"))? {
+            0 => {
+                let bytes: &[u8] = input.stored_block().ok_or_else(|| invalid("bad stored block"))?;
+                out.extend_from_slice(bytes);
+            }
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut input, &mut out, &literals, &distances).map_err(invalid)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut input).map_err(invalid)?;
+                inflate_block(&mut input, &mut out, &literals, &distances).map_err(invalid)?;
+            }
+            _ => return Err(invalid("bad block type")),
+        }
+        if last {
+            break;
+        }
+    }
+
+    let end: usize = 2 + input.byte_position();
+    let checksum: &[u8] = data.get(end..end + 4).ok_or_else(|| invalid("missing checksum"))?;
+    if adler32(&out).to_be_bytes() != checksum {
+        return Err(invalid("checksum mismatch"));
+    }
+    Ok(out)
+}
+
+/// Decodes the symbols of a compressed block into `out`, up to the end of block.
+fn inflate_block(input: &mut BitReader, out: &mut Vec<u8>, literals: &Huffman, distances: &Huffman) -> Result<(), &'static str> {
+    loop {
+        let symbol: usize = literals.decode(input).ok_or("bad literal/length code")? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            257..=285 => {
+                let i: usize = symbol - 257;
+                let length: usize = LENGTH_BASE[i] as usize + input.read(LENGTH_EXTRA[i]).ok_or("truncated")? as usize;
+                let d: usize = distances.decode(input).ok_or("bad distance code")? as usize;
+                if d >= 30 {
+                    return Err("bad distance code");
+                }
+                let distance: usize = DIST_BASE[d] as usize + input.read(DIST_EXTRA[d]).ok_or("truncated")? as usize;
+                if distance > out.len() {
+                    return Err("distance too far back");
+                }
+                // Copies can overlap what they write.
+                let start: usize = out.len() - distance;
+                for k in 0..length {
+                    out.push(out[start + k]);
+                }
+            }
+            _ => return Err("bad literal/length code"),
+        }
+    }
+}
+
+/// The codes of blocks compressed with fixed Huffman codes.
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+/// Reads the codes at the start of a block compressed with dynamic Huffman codes.
+fn dynamic_codes(input: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+    let mut read = |bits: u8| input.read(bits).ok_or("truncated");
+    let literal_count: usize = read(5)? as usize + 257;
+    let distance_count: usize = read(5)? as usize + 1;
+    let code_length_count: usize = read(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        code_length_lengths[i] = read(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_length_lengths);
+
+    // Both sets of lengths form a single sequence, which repeats can cross.
+    let mut lengths: Vec<u8> = Vec::with_capacity(literal_count + distance_count);
+    while lengths.len() < literal_count + distance_count {
+        let symbol: u16 = code_lengths.decode(input).ok_or("bad code length code")?;
+        let mut read = |bits: u8| input.read(bits).ok_or("truncated");
+        let (value, repeat): (u8, u32) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => (*lengths.last().ok_or("repeat of no length")?, 3 + read(2)?),
+            17 => (0, 3 + read(3)?),
+            _ => (0, 11 + read(7)?),
+        };
+        lengths.extend(std::iter::repeat_n(value, repeat as usize));
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err("too many code lengths");
+    }
+    if lengths[256] == 0 {
+        return Err("missing end of block code");
+    }
+    Ok((Huffman::new(&lengths[..literal_count]), Huffman::new(&lengths[literal_count..])))
+}
+
+/// A canonical Huffman code, decoded one bit at a time.
+struct Huffman {
+    /// Number of codes of each length.
+    counts: [u16; 16],
+    /// Symbols ordered by code.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0u16; 16];
+        for &l in lengths {
+            counts[l as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut symbols: Vec<u16> = (0..lengths.len() as u16).filter(|&s| lengths[s as usize] != 0).collect();
+        symbols.sort_by_key(|&s| lengths[s as usize]);
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, input: &mut BitReader) -> Option<u16> {
+        // Codes of each length are consecutive, and come after those of the shorter lengths.
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for len in 1..16 {
+            code |= input.read(1)? as i32;
+            let count: i32 = self.counts[len] as i32;
+            if code - first < count {
+                return self.symbols.get((index + code - first) as usize).copied();
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        None
+    }
+}
+
+/// Reads bits from bytes, least significant bit first.
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    /// Position in bits.
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    /// Reads `bits` bits as a number, the first one being the least significant.
+    fn read(&mut self, bits: u8) -> Option<u32> {
+        let mut value: u32 = 0;
+        for i in 0..bits {
+            let byte: u8 = *self.bytes.get(self.pos / 8)?;
+            value |= (((byte >> (self.pos % 8)) & 1) as u32) << i;
+            self.pos += 1;
+        }
+        Some(value)
+    }
+
+    /// Index of the byte following the current one, if partly read.
+    fn byte_position(&self) -> usize {
+        self.pos.div_ceil(8)
+    }
+
+    /// Reads the length and the bytes of an uncompressed block, which starts at a byte boundary.
+    fn stored_block(&mut self) -> Option<&'a [u8]> {
+        let start: usize = self.byte_position();
+        let header: &[u8] = self.bytes.get(start..start + 4)?;
+        let (len, nlen) = (u16::from_le_bytes([header[0], header[1]]), u16::from_le_bytes([header[2], header[3]]));
+        if len != !nlen {
+            return None;
+        }
+        let data: &'a [u8] = self.bytes.get(start + 4..start + 4 + len as usize)?;
+        self.pos = (start + 4 + len as usize) * 8;
+        Some(data)
+    }
+}
+
+/// Packs bits into bytes, least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    count: u8,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        Self { bytes: vec![], acc: 0, count: 0 }
+    }
+
+    /// Writes the `bits` low bits of `value`.
+    fn write(&mut self, value: u32, bits: u8) {
+        self.acc |= (value as u64) << self.count;
+        self.count += bits;
+        while self.count >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Number of bits written so far.
+    fn bits(&self) -> usize {
+        8 * self.bytes.len() + self.count as usize
+    }
+
+    /// Drops what was written after the first `bits` bits.
+    fn rewind(&mut self, bits: usize) {
+        let (full, rest) = (bits / 8, (bits % 8) as u8);
+        let pending: u64 = if self.bytes.len() > full { self.bytes[full] as u64 } else { self.acc };
+        self.bytes.truncate(full);
+        self.acc = pending & ((1 << rest) - 1);
+        self.count = rest;
+    }
+
+    /// Pads with zeros up to the next byte.
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+
+    /// Writes a Huffman code, which goes most significant bit first.
+    fn write_code(&mut self, code: u32, len: u8) {
+        self.write(code.reverse_bits() >> (32 - len as u32), len);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn adler32_reference() {
+    assert!(adler32(b"Wikipedia") == 0x11E6_0398);
+}
+
+#[test]
+fn inflate_streams() {
+    // From zlib: fixed Huffman codes, then a stored block.
+    let fixed: [u8; 21] = [120, 218, 75, 76, 74, 78, 132, 33, 133, 140, 212, 156, 156, 124, 8, 9, 0, 112, 18, 9, 1];
+    assert_eq!(decompress(&fixed).unwrap(), b"abcabcabcabc hello hello");
+    let stored: [u8; 17] = [120, 1, 1, 6, 0, 249, 255, 115, 116, 111, 114, 101, 100, 9, 60, 2, 146];
+    assert_eq!(decompress(&stored).unwrap(), b"stored");
+
+    // Dynamic Huffman codes, over several blocks
+    fastrand::seed(3);
+    let data: Vec<u8> = (0..200_000).map(|i| if i % 7 == 0 { fastrand::u8(..) } else { b"rustracer"[i % 9] }).collect();
+    assert_eq!(decompress(&compress(&data)).unwrap(), data);
+    assert_eq!(decompress(&compress(&[])).unwrap(), b"");
+
+    let mut corrupt: Vec<u8> = compress(b"checksum");
+    *corrupt.last_mut().unwrap() ^= 1;
+    assert!(decompress(&corrupt).is_err());
+    assert!(decompress(&fixed[..10]).is_err());
+}
+
+#[test]
+fn deflate_sizes() {
+    // Random bytes don't compress: they are stored, in blocks of up to 65535 bytes taking 5 more each.
+    fastrand::seed(5);
+    let random: Vec<u8> = (0..100_000).map(|_| fastrand::u8(..)).collect();
+    let compressed: Vec<u8> = compress(&random);
+    assert!(compressed.len() <= random.len() + 5 * 4 + 6);
+    assert_eq!(decompress(&compressed).unwrap(), random);
+
+    // Repetitions do, down to a few bits per 258 bytes.
+    let text: Vec<u8> = b"rustracer ".repeat(10_000);
+    assert!(compress(&text).len() < 300);
+    assert_eq!(decompress(&compress(&text)).unwrap(), text);
+
+    // Streams checked with the zlib library: an empty block of dynamic codes, and a stored one.
+    assert_eq!(compress(b""), [120, 156, 5, 193, 129, 0, 0, 0, 0, 0, 16, 255, 213, 8, 0, 0, 0, 1]);
+    assert_eq!(compress(b"a"), [120, 156, 1, 1, 0, 254, 255, 97, 0, 98, 0, 98]);
+}
@@ -0,0 +1,52 @@
+use crate::objects::{HitRecord, Object};
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::utils::Interval;
+use crate::vec3::{Color3, Vec3};
+
+use super::{trace, Integrator};
+
+/// Shades the first hit by the fraction of its hemisphere not blocked within `distance`, in grey levels.
+/// Rays escaping the scene are white.
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub distance: f64,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: u32, distance: f64) -> Self {
+        Self {
+            samples,
+            distance,
+        }
+    }
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, r: &Ray, scene: &Scene) -> Color3 {
+        self.radiance_and_hit(r, scene).0
+    }
+
+    fn radiance_and_hit(&self, r: &Ray, scene: &Scene) -> (Color3, Option<HitRecord>) {
+        let Some(rec) = trace(r, scene) else { return (Color3::new(1., 1., 1.), None) };
+        if self.samples == 0 {
+            return (Color3::new(1., 1., 1.), Some(rec))
+        }
+
+        let mut visible: u32 = 0;
+        for _ in 0..self.samples {
+            // Cosine-weighted, so that each unblocked ray counts the same.
+            let mut dir: Vec3 = rec.normal + Vec3::rand_unit();
+            if dir.near_zero() {
+                dir = rec.normal;
+            }
+            let occlusion_t = Interval::new(0.001, self.distance / dir.len());
+            if !scene.world.hit(&Ray::new(rec.p, dir), &occlusion_t, &mut rec.clone()) {
+                visible += 1;
+            }
+        }
+
+        let fraction: f64 = visible as f64 / self.samples as f64;
+        (Color3::new(fraction, fraction, fraction), Some(rec))
+    }
+}
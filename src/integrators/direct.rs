@@ -0,0 +1,59 @@
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vec3::{Color3, Vec3};
+
+use super::{sample_light, trace, Integrator};
+
+/// Only light going straight from the lights to the first diffuse surface, sampled on the lights.
+/// Mirrors and glass are still followed, up to `max_depth` bounces, so that lights can be seen through them.
+pub struct DirectLighting {
+    pub max_depth: u32,
+}
+
+impl DirectLighting {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+        }
+    }
+}
+
+impl Integrator for DirectLighting {
+    fn radiance(&self, r: &Ray, scene: &Scene) -> Color3 {
+        self.radiance_and_hit(r, scene).0
+    }
+
+    fn radiance_and_hit(&self, r: &Ray, scene: &Scene) -> (Color3, Option<HitRecord>) {
+        let mut first_hit: Option<HitRecord> = None;
+        let mut color: Color3 = Color3::new(0., 0., 0.);
+        let mut throughput: Color3 = Color3::new(1., 1., 1.);
+        let mut ray: Ray = *r;
+
+        for bounce in 0..self.max_depth {
+            let Some(rec) = trace(&ray, scene) else {
+                color += throughput.mult(scene.background.color(&ray));
+                break
+            };
+            if bounce == 0 {
+                first_hit = Some(rec.clone());
+            }
+            color += throughput.mult(rec.mat.emitted(&rec));
+
+            let mut r_scattered: Ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.));
+            let mut attenuation: Color3 = Color3::new(0., 0., 0.);
+            if !rec.mat.scatter(&ray, &rec, &mut attenuation, &mut r_scattered) {
+                break
+            }
+
+            if rec.mat.eval(&ray, &rec, r_scattered.dir()).is_some() {
+                color += throughput.mult(sample_light(&ray, &rec, scene, false));
+                break
+            }
+            throughput.multeq(attenuation);
+            ray = r_scattered;
+        }
+
+        (color, first_hit)
+    }
+}
@@ -0,0 +1,124 @@
+use crate::objects::{HitRecord, Object};
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::utils::Interval;
+use crate::vec3::{Color3, Vec3};
+
+mod ambient_occlusion;
+pub use ambient_occlusion::AmbientOcclusion;
+mod direct;
+pub use direct::DirectLighting;
+mod path;
+pub use path::PathTracer;
+mod recursive;
+pub use recursive::RecursiveTracer;
+
+/// A light transport algorithm, computing the light arriving along camera rays.
+///
+/// ```
+/// use rustracer::integrators::Integrator;
+/// use rustracer::objects::{HitRecord, Object};
+/// use rustracer::ray::Ray;
+/// use rustracer::utils::Interval;
+/// use rustracer::vec3::Color3;
+/// use rustracer::Scene;
+///
+/// /// Shows the normals of the first hits.
+/// struct Normals;
+///
+/// impl Integrator for Normals {
+///     fn radiance(&self, r: &Ray, scene: &Scene) -> Color3 {
+///         let mut rec = HitRecord::new();
+///         if !scene.world.hit(r, &Interval::new(0.001, f64::INFINITY), &mut rec) {
+///             return Color3::new(0., 0., 0.);
+///         }
+///         0.5 * (rec.normal + Color3::new(1., 1., 1.))
+///     }
+/// }
+/// ```
+pub trait Integrator: Send + Sync {
+    /// Returns the light coming along `r` toward its origin.
+    fn radiance(&self, r: &Ray, scene: &Scene) -> Color3;
+
+    /// Same as [`Integrator::radiance`], also returning the first hit of `r`, for the output variables.
+    /// By default `r` is traced once more; integrators override it to hand over their own first hit.
+    fn radiance_and_hit(&self, r: &Ray, scene: &Scene) -> (Color3, Option<HitRecord>) {
+        (self.radiance(r, scene), trace(r, scene))
+    }
+}
+
+/// The integrators that can be picked by name, in scene files or on the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegratorKind {
+    Path,
+    Recursive,
+    Direct,
+    AmbientOcclusion,
+}
+
+impl IntegratorKind {
+    pub const NAMES: [&'static str; 4] = ["path", "recursive", "direct", "ao"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "path" => Some(Self::Path),
+            "recursive" => Some(Self::Recursive),
+            "direct" => Some(Self::Direct),
+            "ao" => Some(Self::AmbientOcclusion),
+            _ => None,
+        }
+    }
+
+    /// Creates the integrator. Ambient occlusion ignores `max_depth`, and casts 4 rays within 1 unit.
+    pub fn build(self, max_depth: u32) -> Box<dyn Integrator> {
+        match self {
+            Self::Path => Box::new(PathTracer::new(max_depth)),
+            Self::Recursive => Box::new(RecursiveTracer::new(max_depth)),
+            Self::Direct => Box::new(DirectLighting::new(max_depth)),
+            Self::AmbientOcclusion => Box::new(AmbientOcclusion::new(4, 1.)),
+        }
+    }
+}
+
+/// Returns the closest hit of `r` in the scene, ignoring the few first units to avoid self-intersections.
+pub(crate) fn trace(r: &Ray, scene: &Scene) -> Option<HitRecord> {
+    let mut rec: HitRecord = HitRecord::new();
+    if scene.world.hit(r, &Interval::new(0.001, f64::INFINITY), &mut rec) { Some(rec) } else { None }
+}
+
+/// Weight of a sample drawn with density `pdf`, when `other_pdf` could also have drawn it.
+pub(crate) fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b <= 0. { 1. } else { a / (a + b) }
+}
+
+/// Weight of emission met by a scattered ray, against the light sampling done at its origin.
+/// `bsdf_pdf` is the density with which the ray was scattered, `None` if lights weren't sampled there.
+pub(crate) fn emission_weight(r: &Ray, scene: &Scene, bsdf_pdf: Option<f64>) -> f64 {
+    match bsdf_pdf {
+        Some(bsdf_pdf) => power_heuristic(bsdf_pdf, scene.lights.pdf_value(r.orig(), r.dir())),
+        None => 1.,
+    }
+}
+
+/// Next-event estimation: returns the light reaching `rec` from a point picked on the lights,
+/// weighted against BSDF sampling when `mis` is set.
+pub(crate) fn sample_light(r: &Ray, rec: &HitRecord, scene: &Scene, mis: bool) -> Color3 {
+    let black: Color3 = Color3::new(0., 0., 0.);
+    let Some(sample) = scene.lights.sample(rec.p) else { return black };
+
+    let dir: Vec3 = sample.p - rec.p;
+    let Some((f, bsdf_pdf)) = rec.mat.eval(r, rec, dir) else { return black };
+    if bsdf_pdf <= 0. {
+        return black
+    }
+
+    // The sample point is at t = 1: anything closer is in the way.
+    let light_rec: HitRecord = match trace(&Ray::new(rec.p, dir), scene) {
+        Some(light_rec) if light_rec.t >= 1. - 1e-6 => light_rec,
+        _ => return black,
+    };
+
+    let weight: f64 = if mis { power_heuristic(sample.pdf, bsdf_pdf) } else { 1. };
+    weight / sample.pdf * f.mult(light_rec.mat.emitted(&light_rec))
+}
@@ -0,0 +1,107 @@
+use fastrand::f64;
+
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vec3::{Color3, Vec3};
+
+use super::{emission_weight, sample_light, trace, Integrator};
+
+/// Iterative path tracer with next-event estimation and multiple importance sampling.
+/// After `rr_depth` bounces, paths are randomly ended with Russian roulette, based on how much light they still carry.
+pub struct PathTracer {
+    pub max_depth: u32,
+    pub rr_depth: u32,
+}
+
+impl PathTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+            rr_depth: 3,
+        }
+    }
+}
+
+impl Integrator for PathTracer {
+    fn radiance(&self, r: &Ray, scene: &Scene) -> Color3 {
+        self.radiance_and_hit(r, scene).0
+    }
+
+    fn radiance_and_hit(&self, r: &Ray, scene: &Scene) -> (Color3, Option<HitRecord>) {
+        let mut first_hit: Option<HitRecord> = None;
+        let mut color: Color3 = Color3::new(0., 0., 0.);
+        let mut throughput: Color3 = Color3::new(1., 1., 1.);
+        let mut ray: Ray = *r;
+        let mut bsdf_pdf: Option<f64> = None;
+
+        for bounce in 0..self.max_depth {
+            let Some(rec) = trace(&ray, scene) else {
+                color += throughput.mult(scene.background.color(&ray));
+                break
+            };
+            if bounce == 0 {
+                first_hit = Some(rec.clone());
+            }
+
+            color += emission_weight(&ray, scene, bsdf_pdf) * throughput.mult(rec.mat.emitted(&rec));
+
+            let mut r_scattered: Ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.));
+            let mut attenuation: Color3 = Color3::new(0., 0., 0.);
+            if !rec.mat.scatter(&ray, &rec, &mut attenuation, &mut r_scattered) {
+                break
+            }
+
+            bsdf_pdf = rec.mat.eval(&ray, &rec, r_scattered.dir()).map(|(_, pdf)| pdf);
+            if bsdf_pdf.is_some() {
+                // At the last bounce the scattered ray isn't traced, so the lights get the full weight.
+                let mis: bool = bounce + 1 < self.max_depth;
+                color += throughput.mult(sample_light(&ray, &rec, scene, mis));
+            }
+            throughput.multeq(attenuation);
+
+            if bounce + 1 >= self.rr_depth {
+                let survival: f64 = throughput.x().max(throughput.y()).max(throughput.z()).min(0.95);
+                if f64() >= survival {
+                    break
+                }
+                throughput /= survival;
+            }
+
+            ray = r_scattered;
+        }
+
+        (color, first_hit)
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn last_bounce_keeps_direct_light() {
+    use std::sync::Arc;
+
+    use crate::integrators::{DirectLighting, RecursiveTracer};
+    use crate::materials::{DiffuseLight, Lambertian};
+    use crate::objects::{Objects, Quad};
+    use crate::scene::Background;
+    use crate::vec3::Point3;
+
+    // A floor lit by a square light above it, in the dark.
+    let mut objects = Objects::new();
+    objects.add(Quad::new(Point3::new(-5., 0., -5.), Vec3::new(0., 0., 10.), Vec3::new(10., 0., 0.), Arc::new(Lambertian::new(Color3::new(0.8, 0.8, 0.8)))).unwrap());
+    objects.add(Quad::new(Point3::new(-1., 2., -1.), Vec3::new(2., 0., 0.), Vec3::new(0., 0., 2.), Arc::new(DiffuseLight::new(Color3::new(5., 5., 5.)))).unwrap());
+    let mut scene = Scene::new(objects);
+    scene.background = Background::Solid(Color3::new(0., 0., 0.));
+
+    fastrand::seed(12);
+    let r = Ray::new(Point3::new(0.5, 1., 3.), Vec3::new(0., -1., -3.));
+    let average = |integrator: &dyn Integrator| (0..4000).map(|_| integrator.radiance(&r, &scene).x()).sum::<f64>() / 4000.;
+    let (path, direct) = (average(&PathTracer::new(1)), average(&DirectLighting::new(1)));
+    assert!(direct > 0.1);
+    assert!((path - direct).abs() < 0.03 * direct, "path {} against direct {}", path, direct);
+    let recursive: f64 = average(&RecursiveTracer::new(1));
+    assert!((recursive - direct).abs() < 0.03 * direct, "recursive {} against direct {}", recursive, direct);
+}
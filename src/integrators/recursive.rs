@@ -0,0 +1,67 @@
+use crate::objects::HitRecord;
+use crate::ray::Ray;
+use crate::scene::Scene;
+use crate::vec3::{Color3, Vec3};
+
+use super::{emission_weight, sample_light, trace, Integrator};
+
+/// Follows one scattered ray per bounce by recursion, sampling the lights at each diffuse bounce.
+pub struct RecursiveTracer {
+    pub max_depth: u32,
+}
+
+impl RecursiveTracer {
+    pub fn new(max_depth: u32) -> Self {
+        Self {
+            max_depth,
+        }
+    }
+
+    /// `bsdf_pdf` is the density with which the previous bounce picked `r`, when lights were also sampled from there.
+    fn ray_color(&self, r: &Ray, depth: u32, scene: &Scene, bsdf_pdf: Option<f64>) -> Color3 {
+        if depth < 1 { return Color3::new(0., 0., 0.) }
+
+        match trace(r, scene) {
+            Some(rec) => self.shade(r, &rec, depth, scene, bsdf_pdf),
+            None => scene.background.color(r),
+        }
+    }
+
+    /// The light leaving the hit `rec` of `r` toward its origin.
+    fn shade(&self, r: &Ray, rec: &HitRecord, depth: u32, scene: &Scene, bsdf_pdf: Option<f64>) -> Color3 {
+        // Emission met by chance, weighted against the light sampling of the previous bounce.
+        let mut color: Color3 = emission_weight(r, scene, bsdf_pdf) * rec.mat.emitted(rec);
+
+        let mut r_scattered: Ray = Ray::new(Vec3::new(0., 0., 0.), Vec3::new(0., 0., 0.));
+        let mut attenuation: Color3 = Color3::new(0., 0., 0.);
+        if !rec.mat.scatter(r, rec, &mut attenuation, &mut r_scattered) {
+            return color
+        }
+
+        match rec.mat.eval(r, rec, r_scattered.dir()) {
+            Some((_, scattered_pdf)) => {
+                // At the last bounce the scattered ray isn't traced, so the lights get the full weight.
+                color += sample_light(r, rec, scene, depth > 1);
+                color + attenuation.mult(self.ray_color(&r_scattered, depth-1, scene, Some(scattered_pdf)))
+            }
+            // Specular bounce: only the scattered ray can find lights.
+            None => color + attenuation.mult(self.ray_color(&r_scattered, depth-1, scene, None)),
+        }
+    }
+}
+
+impl Integrator for RecursiveTracer {
+    fn radiance(&self, r: &Ray, scene: &Scene) -> Color3 {
+        self.ray_color(r, self.max_depth, scene, None)
+    }
+
+    fn radiance_and_hit(&self, r: &Ray, scene: &Scene) -> (Color3, Option<HitRecord>) {
+        if self.max_depth < 1 {
+            return (Color3::new(0., 0., 0.), None)
+        }
+        match trace(r, scene) {
+            Some(rec) => (self.shade(r, &rec, self.max_depth, scene, None), Some(rec)),
+            None => (scene.background.color(r), None),
+        }
+    }
+}
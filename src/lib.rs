@@ -0,0 +1,48 @@
+//! A CPU path tracer, grown from [_Ray Tracing in One Weekend_](https://raytracing.github.io/books/RayTracingInOneWeekend.html).
+//!
+//! A [`Scene`] is built from [`objects`] made of [`materials`], then rendered by a [`Camera`] built from a [`CameraConfig`]
+//! with one of the [`integrators`] into an [`image::Framebuffer`], which can be saved as PNG, PPM, HDR or OpenEXR.
+//! Scenes can also be read from JSON files with [`loaders::load_scene`].
+//!
+//! ```
+//! use std::sync::Arc;
+//!
+//! use rustracer::integrators::PathTracer;
+//! use rustracer::materials::Lambertian;
+//! use rustracer::objects::{Objects, Sphere};
+//! use rustracer::vec3::{Color3, Point3};
+//! use rustracer::{CameraConfig, Scene};
+//!
+//! let mut objects = Objects::new();
+//! let red = Arc::new(Lambertian::new(Color3::new(0.8, 0.1, 0.1)));
+//! objects.add(Sphere::new(Point3::new(0., 0., 0.), 1., red));
+//! let scene = Scene::new(objects);
+//!
+//! let cam = CameraConfig {
+//!     image_width: 32,
+//!     samples_per_pixel: 4,
+//!     lookfrom: Point3::new(0., 0., 5.),
+//!     ..Default::default()
+//! }.build()?;
+//!
+//! let fb = cam.render(&scene, &PathTracer::new(5));
+//! assert_eq!((fb.width(), fb.height()), (32, 18));
+//! # Ok::<(), rustracer::Error>(())
+//! ```
+
+pub mod camera;
+pub mod error;
+pub mod image;
+pub mod integrators;
+pub mod loaders;
+pub mod materials;
+pub mod objects;
+pub mod ray;
+pub mod scene;
+pub mod textures;
+pub mod utils;
+pub mod vec3;
+
+pub use camera::{Camera, CameraConfig};
+pub use error::{Error, Result};
+pub use scene::{Background, RenderSettings, Scene, SceneDescription};
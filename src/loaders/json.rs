@@ -0,0 +1,359 @@
+use std::cell::Cell;
+
+use crate::vec3::Vec3;
+
+/// A JSON value, along with the line where it starts, so that errors can point to it.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Json {
+    pub line: usize,
+    pub value: Value,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// Members in the order of the text.
+    Object(Vec<(String, Json)>),
+}
+
+/// A parsing or validation error, at a 1-based line.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct JsonError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl JsonError {
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+/// Parses a whole JSON document.
+pub(crate) fn parse(text: &str) -> Result<Json, JsonError> {
+    let mut parser = Parser { bytes: text.as_bytes(), pos: 0, line: 1 };
+    let json: Json = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(JsonError::new(parser.line, "unexpected data after the end of the document"));
+    }
+    Ok(json)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.bytes.get(self.pos) {
+            match b {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(b) if b == byte => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(b) => Err(JsonError::new(self.line, format!("expected '{}', found '{}'", byte as char, b as char))),
+            None => Err(JsonError::new(self.line, format!("expected '{}', found the end of the file", byte as char))),
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        let Some(b) = self.peek() else { return Err(JsonError::new(self.line, "unexpected end of the file")) };
+        let line: usize = self.line;
+        let value: Value = match b {
+            b'{' => self.object()?,
+            b'[' => self.array()?,
+            b'"' => Value::String(self.string()?),
+            b'-' | b'0'..=b'9' => Value::Number(self.number()?),
+            _ => self.keyword()?,
+        };
+        Ok(Json { line, value })
+    }
+
+    fn object(&mut self) -> Result<Value, JsonError> {
+        self.expect(b'{')?;
+        let mut members: Vec<(String, Json)> = vec![];
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(Value::Object(members));
+        }
+        loop {
+            if self.peek() != Some(b'"') {
+                return Err(JsonError::new(self.line, "expected a member name in quotes"));
+            }
+            let name: String = self.string()?;
+            if members.iter().any(|(n, _)| *n == name) {
+                return Err(JsonError::new(self.line, format!("duplicate member \"{}\"", name)));
+            }
+            self.expect(b':')?;
+            members.push((name, self.value()?));
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(Value::Object(members));
+                }
+                _ => return Err(JsonError::new(self.line, "expected ',' or '}' after an object member")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, JsonError> {
+        self.expect(b'[')?;
+        let mut items: Vec<Json> = vec![];
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(Value::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                _ => return Err(JsonError::new(self.line, "expected ',' or ']' after an array item")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut s: Vec<u8> = vec![];
+        loop {
+            let Some(&b) = self.bytes.get(self.pos) else { return Err(JsonError::new(self.line, "unterminated string")) };
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\n' => return Err(JsonError::new(self.line, "unterminated string")),
+                b'\\' => {
+                    let Some(&escape) = self.bytes.get(self.pos) else { continue };
+                    self.pos += 1;
+                    match escape {
+                        b'"' | b'\\' | b'/' => s.push(escape),
+                        b'b' => s.push(0x08),
+                        b'f' => s.push(0x0C),
+                        b'n' => s.push(b'\n'),
+                        b'r' => s.push(b'\r'),
+                        b't' => s.push(b'\t'),
+                        b'u' => {
+                            let c: char = self.unicode_escape()?;
+                            s.extend(c.encode_utf8(&mut [0; 4]).as_bytes());
+                        }
+                        _ => return Err(JsonError::new(self.line, format!("invalid escape '\\{}'", escape as char))),
+                    }
+                }
+                _ => s.push(b),
+            }
+        }
+        String::from_utf8(s).map_err(|_| JsonError::new(self.line, "invalid UTF-8 in string"))
+    }
+
+    /// Reads the digits of a `\u` escape, and of the low surrogate following it if needed.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let hex = |parser: &mut Self| -> Result<u32, JsonError> {
+            let digits = parser.bytes.get(parser.pos..parser.pos + 4).and_then(|d| std::str::from_utf8(d).ok());
+            let code = digits.and_then(|d| u32::from_str_radix(d, 16).ok());
+            parser.pos += 4;
+            code.ok_or_else(|| JsonError::new(parser.line, "invalid \\u escape"))
+        };
+
+        let high: u32 = hex(self)?;
+        let code: u32 = if (0xD800..0xDC00).contains(&high) {
+            if self.bytes.get(self.pos..self.pos + 2) != Some(b"\\u") {
+                return Err(JsonError::new(self.line, "unpaired surrogate in \\u escape"));
+            }
+            self.pos += 2;
+            let low: u32 = hex(self)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(JsonError::new(self.line, "unpaired surrogate in \\u escape"));
+            }
+            0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            high
+        };
+        char::from_u32(code).ok_or_else(|| JsonError::new(self.line, "invalid \\u escape"))
+    }
+
+    fn number(&mut self) -> Result<f64, JsonError> {
+        let start: usize = self.pos;
+        while let Some(b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') = self.bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        let text: &str = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap_or_default();
+        text.parse().map_err(|_| JsonError::new(self.line, format!("invalid number \"{}\"", text)))
+    }
+
+    fn keyword(&mut self) -> Result<Value, JsonError> {
+        let rest: &[u8] = &self.bytes[self.pos..];
+        for (word, value) in [("true", Value::Bool(true)), ("false", Value::Bool(false)), ("null", Value::Null)] {
+            if rest.starts_with(word.as_bytes()) {
+                self.pos += word.len();
+                return Ok(value);
+            }
+        }
+        Err(JsonError::new(self.line, format!("unexpected character '{}'", rest[0] as char)))
+    }
+}
+
+// ------------------------- Typed access
+
+impl Json {
+    fn kind(&self) -> &'static str {
+        match self.value {
+            Value::Null => "null",
+            Value::Bool(_) => "a boolean",
+            Value::Number(_) => "a number",
+            Value::String(_) => "a string",
+            Value::Array(_) => "an array",
+            Value::Object(_) => "an object",
+        }
+    }
+
+    fn mismatch(&self, expected: &str) -> JsonError {
+        JsonError::new(self.line, format!("expected {}, found {}", expected, self.kind()))
+    }
+
+    pub fn as_bool(&self) -> Result<bool, JsonError> {
+        match self.value {
+            Value::Bool(b) => Ok(b),
+            _ => Err(self.mismatch("a boolean")),
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64, JsonError> {
+        match self.value {
+            Value::Number(n) => Ok(n),
+            _ => Err(self.mismatch("a number")),
+        }
+    }
+
+    /// A number that is a whole, non-negative value fitting in `u64`.
+    pub fn as_u64(&self) -> Result<u64, JsonError> {
+        let n: f64 = self.as_f64()?;
+        if n < 0. || n.fract() != 0. || n > u64::MAX as f64 {
+            return Err(JsonError::new(self.line, format!("expected a non-negative integer, found {}", n)));
+        }
+        Ok(n as u64)
+    }
+
+    pub fn as_u32(&self) -> Result<u32, JsonError> {
+        u32::try_from(self.as_u64()?).map_err(|_| JsonError::new(self.line, "integer too large"))
+    }
+
+    pub fn as_str(&self) -> Result<&str, JsonError> {
+        match &self.value {
+            Value::String(s) => Ok(s),
+            _ => Err(self.mismatch("a string")),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Json], JsonError> {
+        match &self.value {
+            Value::Array(items) => Ok(items),
+            _ => Err(self.mismatch("an array")),
+        }
+    }
+
+    /// An array of 3 numbers.
+    pub fn as_vec3(&self) -> Result<Vec3, JsonError> {
+        match self.as_array()? {
+            [x, y, z] => Ok(Vec3::new(x.as_f64()?, y.as_f64()?, z.as_f64()?)),
+            items => Err(JsonError::new(self.line, format!("expected an array of 3 numbers, found {} items", items.len()))),
+        }
+    }
+
+    pub fn as_object(&self) -> Result<Fields<'_>, JsonError> {
+        match &self.value {
+            Value::Object(members) => Ok(Fields {
+                line: self.line,
+                members,
+                used: vec![Cell::new(false); members.len()],
+            }),
+            _ => Err(self.mismatch("an object")),
+        }
+    }
+}
+
+/// The members of an object, keeping track of the ones read so that unknown ones can be reported.
+pub(crate) struct Fields<'a> {
+    pub line: usize,
+    members: &'a [(String, Json)],
+    used: Vec<Cell<bool>>,
+}
+
+impl<'a> Fields<'a> {
+    pub fn get(&self, name: &str) -> Option<&'a Json> {
+        let i: usize = self.members.iter().position(|(n, _)| n == name)?;
+        self.used[i].set(true);
+        Some(&self.members[i].1)
+    }
+
+    pub fn required(&self, name: &str) -> Result<&'a Json, JsonError> {
+        self.get(name).ok_or_else(|| JsonError::new(self.line, format!("missing \"{}\"", name)))
+    }
+
+    /// All the members, marked as read.
+    pub fn members(&self) -> &'a [(String, Json)] {
+        self.used.iter().for_each(|u| u.set(true));
+        self.members
+    }
+
+    /// Fails on the first member that wasn't read, which is likely a typo.
+    pub fn finish(&self) -> Result<(), JsonError> {
+        match self.members.iter().zip(&self.used).find(|(_, used)| !used.get()) {
+            Some(((name, json), _)) => Err(JsonError::new(json.line, format!("unknown member \"{}\"", name))),
+            None => Ok(()),
+        }
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn json_values_and_errors() {
+    let json: Json = parse("{\n  \"a\": [1, -2.5e1, true, null],\n  \"b\": \"x\\\"\\u00e9\\ud83d\\ude00\"\n}").unwrap();
+    let fields = json.as_object().unwrap();
+    let a = fields.required("a").unwrap();
+    assert_eq!(a.line, 2);
+    let items = a.as_array().unwrap();
+    assert_eq!(items[1].as_f64().unwrap(), -25.);
+    assert!(items[2].as_bool().unwrap() && items[3].value == Value::Null);
+    assert!(fields.finish().is_err_and(|e| e.line == 3 && e.message.contains("\"b\"")));
+    assert_eq!(fields.required("b").unwrap().as_str().unwrap(), "x\"é😀");
+    assert!(fields.finish().is_ok());
+
+    assert_eq!(parse("{\n\"a\": 1,\n\"a\": 2}").unwrap_err().line, 3);
+    assert_eq!(parse("[1,\n2,\n]").unwrap_err().line, 3);
+    assert_eq!(parse("[1 2]").unwrap_err().message, "expected ',' or ']' after an array item");
+    assert_eq!(parse("\n\n{\"a\": \"b").unwrap_err().line, 3);
+    assert_eq!(parse("[\"\\ud83d\\u0041\"]").unwrap_err().message, "unpaired surrogate in \\u escape");
+    assert!(parse("[1.5]").unwrap().as_array().unwrap()[0].as_u32().is_err());
+}
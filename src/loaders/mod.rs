@@ -0,0 +1,40 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+mod json;
+mod obj;
+pub use obj::load_obj;
+mod ply;
+pub use ply::load_ply;
+mod scene;
+pub use scene::{load_scene, load_scene_with_seed};
+
+/// An asset file that couldn't be read, with the place where it went wrong.
+#[derive(Debug)]
+pub struct LoadError {
+    pub path: PathBuf,
+    /// 1-based line of the error, for text formats.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl LoadError {
+    pub fn new(path: &Path, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.message),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
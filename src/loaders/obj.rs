@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::SplitWhitespace;
+use std::sync::Arc;
+
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::objects::TriangleMesh;
+use crate::textures::{ImageTexture, SolidColor, Texture};
+use crate::vec3::{Color3, Point3, Vec3};
+
+use super::LoadError;
+
+/// A face corner, as 0-based indices into the position, texture coordinate and normal lists.
+type Corner = (usize, Option<usize>, Option<usize>);
+
+/// Loads a Wavefront OBJ file, along with the MTL libraries it references.
+/// Returns one mesh per material used by the faces. Faces before any `usemtl` get a grey diffuse material.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<Vec<TriangleMesh>, LoadError> {
+    let path: &Path = path.as_ref();
+    let text: String = fs::read_to_string(path).map_err(|e| LoadError::new(path, None, e.to_string()))?;
+
+    let mut positions: Vec<Point3> = vec![];
+    let mut uvs: Vec<(f64, f64)> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    // Faces of each material, in order of first use.
+    let mut groups: Vec<(Option<String>, Vec<[Corner; 3]>)> = vec![(None, vec![])];
+    let mut current: usize = 0;
+
+    for (i, line) in text.lines().enumerate() {
+        let mut line = Line::new(path, i + 1, line);
+        let Some(keyword) = line.tokens.next() else { continue };
+
+        match keyword {
+            "v" => positions.push(line.vec3()?),
+            "vt" => {
+                let u: f64 = line.f64()?;
+                let v: f64 = line.opt_f64()?.unwrap_or(0.);
+                uvs.push((u, v));
+            }
+            "vn" => normals.push(line.vec3()?),
+            "f" => {
+                let corners: Vec<Corner> = line.tokens.clone()
+                    .map(|token| line.corner(token, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<_, _>>()?;
+                if corners.len() < 3 {
+                    return Err(line.error("a face needs at least 3 vertices"))
+                }
+                for k in 1..corners.len() - 1 {
+                    groups[current].1.push([corners[0], corners[k], corners[k + 1]]);
+                }
+            }
+            "usemtl" => {
+                let name: String = line.rest()?;
+                if !materials.contains_key(&name) {
+                    return Err(line.error(format!("unknown material `{}`", name)))
+                }
+                current = match groups.iter().position(|(mat, _)| mat.as_deref() == Some(&name)) {
+                    Some(index) => index,
+                    None => {
+                        groups.push((Some(name), vec![]));
+                        groups.len() - 1
+                    }
+                };
+            }
+            "mtllib" => {
+                let dir: &Path = path.parent().unwrap_or(Path::new(""));
+                for file in line.tokens.clone() {
+                    materials.extend(load_mtl(&dir.join(file)).map_err(|e| match e.line {
+                        // A missing library is reported at the line referencing it.
+                        None => line.error(e.to_string()),
+                        Some(_) => e,
+                    })?);
+                }
+            }
+            // Groups, smoothing groups, lines, points and free-form geometry don't matter here.
+            _ => {}
+        }
+    }
+
+    let mut textures: HashMap<PathBuf, Arc<dyn Texture>> = HashMap::new();
+    let mut meshes: Vec<TriangleMesh> = vec![];
+    for (name, faces) in groups.into_iter().filter(|(_, faces)| !faces.is_empty()) {
+        let mat: Arc<dyn Material> = match name {
+            Some(name) => materials[&name].to_material(&mut textures)?,
+            None => Arc::new(Lambertian::new(Color3::new(0.8, 0.8, 0.8))),
+        };
+        meshes.push(build_mesh(&faces, &positions, &uvs, &normals, mat).map_err(|e| LoadError::new(path, None, e.to_string()))?);
+    }
+    Ok(meshes)
+}
+
+/// Gathers the vertices used by `faces` into a mesh of their own.
+fn build_mesh(faces: &[[Corner; 3]], positions: &[Point3], uvs: &[(f64, f64)], normals: &[Vec3], mat: Arc<dyn Material>) -> crate::Result<TriangleMesh> {
+    let has_uvs: bool = faces.iter().flatten().all(|c| c.1.is_some());
+    let has_normals: bool = faces.iter().flatten().all(|c| c.2.is_some());
+
+    let mut remap: HashMap<Corner, u32> = HashMap::new();
+    let (mut mesh_positions, mut mesh_uvs, mut mesh_normals) = (vec![], vec![], vec![]);
+    let indices: Vec<[u32; 3]> = faces.iter().map(|face| face.map(|corner| {
+        *remap.entry(corner).or_insert_with(|| {
+            mesh_positions.push(positions[corner.0]);
+            if has_uvs { mesh_uvs.push(uvs[corner.1.unwrap()]) }
+            if has_normals { mesh_normals.push(normals[corner.2.unwrap()]) }
+            (mesh_positions.len() - 1) as u32
+        })
+    })).collect();
+
+    let mut mesh = TriangleMesh::new(mesh_positions, indices, mat)?;
+    if has_uvs { mesh = mesh.with_uvs(mesh_uvs)? }
+    if has_normals { mesh = mesh.with_normals(mesh_normals)? }
+    Ok(mesh)
+}
+
+
+/// The subset of an MTL material this renderer understands.
+#[derive(Debug, Clone)]
+struct MtlMaterial {
+    kd: Color3,
+    ks: Color3,
+    ns: f64,
+    ni: f64,
+    d: f64,
+    ke: Color3,
+    illum: u32,
+    map_kd: Option<PathBuf>,
+    map_ks: Option<PathBuf>,
+    map_ke: Option<PathBuf>,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            kd: Color3::new(0.8, 0.8, 0.8),
+            ks: Color3::new(0., 0., 0.),
+            ns: 0.,
+            ni: 1.5,
+            d: 1.,
+            ke: Color3::new(0., 0., 0.),
+            illum: 2,
+            map_kd: None,
+            map_ks: None,
+            map_ke: None,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Picks the closest material of the crate: a light for emissive materials, glass for transparent
+    /// or refracting illumination models, metal for mirror-like or mostly specular ones, diffuse otherwise.
+    /// Texture maps replace the matching colors. Images are loaded once into `textures`.
+    fn to_material(&self, textures: &mut HashMap<PathBuf, Arc<dyn Texture>>) -> Result<Arc<dyn Material>, LoadError> {
+        let max = |c: Color3| c.x().max(c.y()).max(c.z());
+        let mut texture = |color: Color3, map: &Option<PathBuf>| -> Result<Arc<dyn Texture>, LoadError> {
+            let Some(path) = map else { return Ok(Arc::new(SolidColor::new(color))) };
+            if let Some(texture) = textures.get(path) {
+                return Ok(texture.clone());
+            }
+            let texture: Arc<dyn Texture> = Arc::new(ImageTexture::open(path)?);
+            textures.insert(path.clone(), texture.clone());
+            Ok(texture)
+        };
+
+        Ok(if max(self.ke) > 0. {
+            Arc::new(DiffuseLight::from_texture(texture(self.ke, &self.map_ke)?))
+        } else if self.d < 1. || matches!(self.illum, 4 | 6 | 7 | 9) {
+            Arc::new(Dielectric::new(self.ni))
+        } else if self.illum == 3 || max(self.ks) > max(self.kd) {
+            // Blinn-Phong exponent to roughness.
+            let fuzz: f64 = (2. / (self.ns.max(0.) + 2.)).sqrt();
+            Arc::new(Metal::from_texture(texture(self.ks, &self.map_ks)?, fuzz))
+        } else {
+            Arc::new(Lambertian::from_texture(texture(self.kd, &self.map_kd)?))
+        })
+    }
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>, LoadError> {
+    let text: String = fs::read_to_string(path).map_err(|e| LoadError::new(path, None, e.to_string()))?;
+    let dir: &Path = path.parent().unwrap_or(Path::new(""));
+
+    let mut materials: HashMap<String, MtlMaterial> = HashMap::new();
+    let mut current: Option<(String, MtlMaterial)> = None;
+
+    for (i, line) in text.lines().enumerate() {
+        let mut line = Line::new(path, i + 1, line);
+        let Some(keyword) = line.tokens.next() else { continue };
+
+        if keyword == "newmtl" {
+            if let Some((name, mat)) = current.take() {
+                materials.insert(name, mat);
+            }
+            current = Some((line.rest()?, MtlMaterial::default()));
+            continue
+        }
+        let Some((_, mat)) = current.as_mut() else {
+            return Err(line.error(format!("`{}` before any `newmtl`", keyword)))
+        };
+
+        match keyword {
+            "Kd" => mat.kd = line.vec3()?,
+            "Ks" => mat.ks = line.vec3()?,
+            "Ke" => mat.ke = line.vec3()?,
+            "Ns" => mat.ns = line.f64()?,
+            "Ni" => mat.ni = line.f64()?,
+            "d" => mat.d = line.f64()?,
+            "Tr" => mat.d = 1. - line.f64()?,
+            "illum" => mat.illum = line.f64()? as u32,
+            "map_Kd" => mat.map_kd = Some(dir.join(line.map_file()?)),
+            "map_Ks" => mat.map_ks = Some(dir.join(line.map_file()?)),
+            "map_Ke" => mat.map_ke = Some(dir.join(line.map_file()?)),
+            _ => {}
+        }
+    }
+
+    if let Some((name, mat)) = current {
+        materials.insert(name, mat);
+    }
+    Ok(materials)
+}
+
+
+/// A line of a text asset being parsed, able to report errors at its position.
+struct Line<'a> {
+    path: &'a Path,
+    number: usize,
+    text: &'a str,
+    tokens: SplitWhitespace<'a>,
+}
+
+impl<'a> Line<'a> {
+    fn new(path: &'a Path, number: usize, text: &'a str) -> Self {
+        let text: &str = text.split('#').next().unwrap_or("").trim();
+        Self { path, number, text, tokens: text.split_whitespace() }
+    }
+
+    fn error(&self, message: impl Into<String>) -> LoadError {
+        LoadError::new(self.path, Some(self.number), message)
+    }
+
+    fn opt_f64(&mut self) -> Result<Option<f64>, LoadError> {
+        self.tokens.next()
+            .map(|token| token.parse::<f64>().map_err(|_| self.error(format!("invalid number `{}`", token))))
+            .transpose()
+    }
+
+    fn f64(&mut self) -> Result<f64, LoadError> {
+        self.opt_f64()?.ok_or_else(|| self.error("missing number"))
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, LoadError> {
+        Ok(Vec3::new(self.f64()?, self.f64()?, self.f64()?))
+    }
+
+    /// Everything after the keyword, such as a name containing spaces.
+    fn rest(&self) -> Result<String, LoadError> {
+        let rest: &str = self.text.split_once(char::is_whitespace).map_or("", |(_, rest)| rest.trim());
+        if rest.is_empty() {
+            return Err(self.error("missing name"))
+        }
+        Ok(rest.to_string())
+    }
+
+    /// The file name of a texture map statement, which comes after its options.
+    fn map_file(&self) -> Result<&'a str, LoadError> {
+        self.tokens.clone().last().ok_or_else(|| self.error("missing file name"))
+    }
+
+    /// Parses a `v`, `v/vt`, `v//vn` or `v/vt/vn` face corner. Negative indices count back from the last element.
+    fn corner(&self, token: &str, positions: usize, uvs: usize, normals: usize) -> Result<Corner, LoadError> {
+        let resolve = |index: &str, len: usize| -> Result<usize, LoadError> {
+            let i: i64 = index.parse().map_err(|_| self.error(format!("invalid index `{}`", index)))?;
+            let resolved: i64 = if i < 0 { len as i64 + i } else { i - 1 };
+            if i == 0 || resolved < 0 || resolved >= len as i64 {
+                return Err(self.error(format!("index {} out of range", i)))
+            }
+            Ok(resolved as usize)
+        };
+
+        let mut parts = token.split('/');
+        let v: usize = resolve(parts.next().unwrap_or(""), positions)?;
+        let vt: Option<usize> = match parts.next() {
+            Some("") | None => None,
+            Some(index) => Some(resolve(index, uvs)?),
+        };
+        let vn: Option<usize> = match parts.next() {
+            Some("") | None => None,
+            Some(index) => Some(resolve(index, normals)?),
+        };
+        Ok((v, vt, vn))
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn obj_with_materials() {
+    let dir: PathBuf = std::env::temp_dir().join("rustracer_obj_with_materials");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("quad.mtl"), "newmtl red\nKd 0.8 0.1 0.1\n\nnewmtl glass\nd 0.2\nNi 1.45\n").unwrap();
+    fs::write(dir.join("quad.obj"), "\
+mtllib quad.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+usemtl red
+f 1//1 2//1 3//1 4//1 # a quad
+usemtl glass
+f -4 -2 -1
+").unwrap();
+
+    let meshes = load_obj(dir.join("quad.obj")).unwrap();
+
+    assert!(meshes.len() == 2);
+    assert!(meshes[0].triangle_count() == 2 && meshes[0].positions().len() == 4 && meshes[0].normals().len() == 4);
+    assert!(meshes[1].triangle_count() == 1 && meshes[1].normals().is_empty());
+
+    // Texture maps are loaded with the materials.
+    fs::write(dir.join("textured.mtl"), "newmtl wood\nmap_Kd wood.ppm\n").unwrap();
+    fs::write(dir.join("textured.obj"), "mtllib textured.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nusemtl wood\nf 1/1 2/2 3/3\n").unwrap();
+    let _ = fs::remove_file(dir.join("wood.ppm"));
+    assert!(load_obj(dir.join("textured.obj")).err().unwrap().to_string().contains("wood.ppm"));
+    fs::write(dir.join("wood.ppm"), b"P6 1 1 255 \x80\x40\x20").unwrap();
+    assert!(load_obj(dir.join("textured.obj")).unwrap()[0].uvs().len() == 3);
+}
+#[test]
+fn obj_error_line() {
+    let dir: PathBuf = std::env::temp_dir().join("rustracer_obj_error_line");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("broken.obj"), "v 0 0 0\nv 1 0 0\nv 1 1 zero\nf 1 2 3\n").unwrap();
+    fs::write(dir.join("range.obj"), "v 0 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 4\n").unwrap();
+
+    let err = load_obj(dir.join("broken.obj")).err().unwrap();
+    assert!(err.line == Some(3));
+    let err = load_obj(dir.join("range.obj")).err().unwrap();
+    assert!(err.line == Some(5));
+}
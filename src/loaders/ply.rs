@@ -0,0 +1,409 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::materials::Material;
+use crate::objects::TriangleMesh;
+use crate::utils::srgb_to_linear;
+use crate::vec3::{Color3, Point3, Vec3};
+
+use super::LoadError;
+
+/// Loads a PLY mesh, in ASCII or binary (little or big endian) format.
+/// Per-vertex normals, texture coordinates and colors are kept when present. 8-bit colors are taken as sRGB.
+/// Polygons are split into triangles, and elements other than vertices and faces are skipped.
+pub fn load_ply(path: impl AsRef<Path>, mat: Arc<dyn Material>) -> Result<TriangleMesh, LoadError> {
+    let path: &Path = path.as_ref();
+    let data: Vec<u8> = fs::read(path).map_err(|e| LoadError::new(path, None, e.to_string()))?;
+
+    let (header, body_start) = Header::parse(path, &data)?;
+    let mut body = Body { path, format: header.format, data: &data, pos: body_start, line: header.lines + 1 };
+
+    let mut positions: Vec<Point3> = vec![];
+    let mut normals: Vec<Vec3> = vec![];
+    let mut uvs: Vec<(f64, f64)> = vec![];
+    let mut colors: Vec<Color3> = vec![];
+    let mut indices: Vec<[u32; 3]> = vec![];
+
+    for element in header.elements.iter() {
+        let find = |names: &[&str]| element.properties.iter().position(|p| names.contains(&p.name.as_str()));
+        let xyz = [find(&["x"]), find(&["y"]), find(&["z"])];
+        let nxyz = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+        let uv = [find(&["u", "s", "texture_u", "texture_s"]), find(&["v", "t", "texture_v", "texture_t"])];
+        let rgb = [find(&["red", "r", "diffuse_red"]), find(&["green", "g", "diffuse_green"]), find(&["blue", "b", "diffuse_blue"])];
+        let face = find(&["vertex_indices", "vertex_index"]);
+
+        if element.name == "vertex" && xyz.iter().any(Option::is_none) {
+            return Err(LoadError::new(path, None, "vertices need x, y and z properties"))
+        }
+
+        let mut values: Vec<f64> = vec![0.; element.properties.len()];
+        let mut list: Vec<f64> = vec![];
+        for _ in 0..element.count {
+            body.start_element();
+            for (k, property) in element.properties.iter().enumerate() {
+                match property.kind {
+                    Kind::Scalar(ty) => values[k] = body.value(ty)?,
+                    Kind::List(count_ty, item_ty) => {
+                        let count: f64 = body.value(count_ty)?;
+                        list.clear();
+                        for _ in 0..count as usize {
+                            let item: f64 = body.value(item_ty)?;
+                            if Some(k) == face {
+                                list.push(item);
+                            }
+                        }
+                    }
+                }
+            }
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let [x, y, z] = xyz.map(|k| values[k.unwrap()]);
+                    positions.push(Point3::new(x, y, z));
+                    if let [Some(nx), Some(ny), Some(nz)] = nxyz {
+                        normals.push(Vec3::new(values[nx], values[ny], values[nz]));
+                    }
+                    if let [Some(u), Some(v)] = uv {
+                        uvs.push((values[u], values[v]));
+                    }
+                    if let [Some(r), Some(g), Some(b)] = rgb {
+                        let channel = |k: usize| match element.properties[k].kind {
+                            Kind::Scalar(ty) if !ty.is_float() => srgb_to_linear(values[k] / ty.max()),
+                            _ => values[k],
+                        };
+                        colors.push(Color3::new(channel(r), channel(g), channel(b)));
+                    }
+                }
+                "face" if face.is_some() => {
+                    if list.len() < 3 {
+                        return Err(body.error("a face needs at least 3 vertices"))
+                    }
+                    let vertex_count: usize = header.vertex_count();
+                    if let Some(&i) = list.iter().find(|&&i| i < 0. || i as usize >= vertex_count) {
+                        return Err(body.error(format!("vertex index {} out of range", i)))
+                    }
+                    for k in 1..list.len() - 1 {
+                        indices.push([list[0] as u32, list[k] as u32, list[k + 1] as u32]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let invalid = |e: crate::Error| LoadError::new(path, None, e.to_string());
+    let mut mesh = TriangleMesh::new(positions, indices, mat).map_err(invalid)?;
+    if !normals.is_empty() { mesh = mesh.with_normals(normals).map_err(invalid)? }
+    if !uvs.is_empty() { mesh = mesh.with_uvs(uvs).map_err(invalid)? }
+    if !colors.is_empty() { mesh = mesh.with_colors(colors).map_err(invalid)? }
+    Ok(mesh)
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+
+    /// The value standing for full intensity, for integer colors.
+    fn max(&self) -> f64 {
+        match self {
+            Self::I8 => i8::MAX as f64,
+            Self::I16 => i16::MAX as f64,
+            Self::U16 => u16::MAX as f64,
+            _ => u8::MAX as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Scalar(Scalar),
+    /// Type of the item count, then of the items.
+    List(Scalar, Scalar),
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    kind: Kind,
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+#[derive(Debug, Clone)]
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+    /// Number of lines of the header, `end_header` included.
+    lines: usize,
+}
+
+impl Header {
+    /// Parses the header at the start of `data`, and returns it with the offset of the body.
+    fn parse(path: &Path, data: &[u8]) -> Result<(Self, usize), LoadError> {
+        let mut format: Option<Format> = None;
+        let mut elements: Vec<Element> = vec![];
+        let mut pos: usize = 0;
+        let mut number: usize = 0;
+
+        loop {
+            let Some(len) = data[pos..].iter().position(|&b| b == b'\n') else {
+                return Err(LoadError::new(path, Some(number + 1), "missing `end_header`"))
+            };
+            number += 1;
+            let line: &str = std::str::from_utf8(&data[pos..pos + len])
+                .map_err(|_| LoadError::new(path, Some(number), "header is not text"))?
+                .trim();
+            pos += len + 1;
+            let error = |message: String| LoadError::new(path, Some(number), message);
+
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            match tokens.as_slice() {
+                ["ply"] if number == 1 => {}
+                _ if number == 1 => return Err(error("not a PLY file".to_string())),
+                ["format", name, _version] => {
+                    format = Some(match *name {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::BinaryLittleEndian,
+                        "binary_big_endian" => Format::BinaryBigEndian,
+                        _ => return Err(error(format!("unknown format `{}`", name))),
+                    });
+                }
+                ["element", name, count] => elements.push(Element {
+                    name: name.to_string(),
+                    count: count.parse().map_err(|_| error(format!("invalid element count `{}`", count)))?,
+                    properties: vec![],
+                }),
+                ["property", rest @ ..] => {
+                    let scalar = |name: &str| Scalar::parse(name).ok_or_else(|| error(format!("unknown type `{}`", name)));
+                    let (kind, name) = match rest {
+                        ["list", count_ty, item_ty, name] => (Kind::List(scalar(count_ty)?, scalar(item_ty)?), name),
+                        [ty, name] => (Kind::Scalar(scalar(ty)?), name),
+                        _ => return Err(error("invalid property".to_string())),
+                    };
+                    let Some(element) = elements.last_mut() else {
+                        return Err(error("property before any element".to_string()))
+                    };
+                    element.properties.push(Property { name: name.to_string(), kind });
+                }
+                ["end_header"] => break,
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => return Err(error(format!("unexpected `{}`", line))),
+            }
+        }
+
+        let format: Format = format.ok_or_else(|| LoadError::new(path, None, "missing format"))?;
+        Ok((Self { format, elements, lines: number }, pos))
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.elements.iter().find(|e| e.name == "vertex").map_or(0, |e| e.count)
+    }
+}
+
+/// Reads the values following the header.
+struct Body<'a> {
+    path: &'a Path,
+    format: Format,
+    data: &'a [u8],
+    pos: usize,
+    /// Line of the next value, for ASCII files.
+    line: usize,
+}
+
+impl Body<'_> {
+    fn error(&self, message: impl Into<String>) -> LoadError {
+        match self.format {
+            Format::Ascii => LoadError::new(self.path, Some(self.line), message),
+            _ => LoadError::new(self.path, None, format!("{} (at byte {})", message.into(), self.pos)),
+        }
+    }
+
+    /// Moves to the line of the next element, for ASCII files.
+    fn start_element(&mut self) {
+        if self.format == Format::Ascii {
+            self.skip_whitespace();
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(&b) = self.data.get(self.pos) {
+            if !b.is_ascii_whitespace() { break }
+            if b == b'\n' { self.line += 1 }
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self, ty: Scalar) -> Result<f64, LoadError> {
+        if self.format == Format::Ascii {
+            self.skip_whitespace();
+            let start: usize = self.pos;
+            while self.data.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace()) {
+                self.pos += 1;
+            }
+            if start == self.pos {
+                return Err(self.error("unexpected end of file"))
+            }
+            let token: &str = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or("");
+            return token.parse::<f64>().map_err(|_| self.error(format!("invalid number `{}`", token)))
+        }
+
+        let Some(bytes) = self.data.get(self.pos..self.pos + ty.size()) else {
+            return Err(self.error("unexpected end of file"))
+        };
+        self.pos += ty.size();
+        let big: bool = self.format == Format::BinaryBigEndian;
+        macro_rules! read {
+            ($t:ty) => {{
+                let raw: [u8; std::mem::size_of::<$t>()] = bytes.try_into().unwrap();
+                (if big { <$t>::from_be_bytes(raw) } else { <$t>::from_le_bytes(raw) }) as f64
+            }};
+        }
+        Ok(match ty {
+            Scalar::I8 => read!(i8),
+            Scalar::U8 => read!(u8),
+            Scalar::I16 => read!(i16),
+            Scalar::U16 => read!(u16),
+            Scalar::I32 => read!(i32),
+            Scalar::U32 => read!(u32),
+            Scalar::F32 => read!(f32),
+            Scalar::F64 => read!(f64),
+        })
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn ply_formats() {
+    use crate::materials::Lambertian;
+
+    let header = |format: &str| format!("\
+ply
+format {} 1.0
+comment a colored quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+", format);
+    let vertices: [([f32; 3], [u8; 3]); 4] = [
+        ([0., 0., 0.], [255, 0, 0]),
+        ([1., 0., 0.], [0, 255, 0]),
+        ([1., 1., 0.], [0, 0, 255]),
+        ([0., 1., 0.], [255, 255, 255]),
+    ];
+
+    let mut ascii: Vec<u8> = header("ascii").into_bytes();
+    let mut little: Vec<u8> = header("binary_little_endian").into_bytes();
+    let mut big: Vec<u8> = header("binary_big_endian").into_bytes();
+    for (p, c) in vertices {
+        ascii.extend(format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]).bytes());
+        for x in p {
+            little.extend(x.to_le_bytes());
+            big.extend(x.to_be_bytes());
+        }
+        little.extend(c);
+        big.extend(c);
+    }
+    ascii.extend(b"4 0 1 2 3\n");
+    little.push(4);
+    big.push(4);
+    for i in 0..4i32 {
+        little.extend(i.to_le_bytes());
+        big.extend(i.to_be_bytes());
+    }
+
+    let dir = std::env::temp_dir().join("rustracer_ply_formats");
+    fs::create_dir_all(&dir).unwrap();
+    let mat = Arc::new(Lambertian::new(Color3::new(1., 1., 1.)));
+    for (name, data) in [("ascii.ply", ascii), ("little.ply", little), ("big.ply", big)] {
+        fs::write(dir.join(name), data).unwrap();
+        let mesh = load_ply(dir.join(name), mat.clone()).unwrap();
+
+        assert!(mesh.triangle_count() == 2);
+        assert!(mesh.positions()[2] == Point3::new(1., 1., 0.));
+        assert!(mesh.colors()[1] == Color3::new(0., 1., 0.));
+    }
+}
+#[test]
+fn ply_error_line() {
+    let dir = std::env::temp_dir().join("rustracer_ply_error_line");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("broken.ply"), "\
+ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0
+1 0 0
+1 1 0
+3 0 1 5
+").unwrap();
+
+    let mat = Arc::new(crate::materials::Lambertian::new(Color3::new(1., 1., 1.)));
+    let err = load_ply(dir.join("broken.ply"), mat).err().unwrap();
+    assert!(err.line == Some(13));
+}
@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::camera::CameraConfig;
+use crate::image::{Denoiser, ExrCompression, ExrPixelType, ToneMap, ToneMapping};
+use crate::integrators::IntegratorKind;
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::objects::{Cuboid, Disk, Objects, Quad, Sphere, Triangle};
+use crate::scene::{Background, RenderSettings, Scene, SceneDescription};
+use crate::textures::{Checker, Filter, ImageTexture, NoisePattern, NoiseTexture, SolidColor, Texture, Wrap};
+use crate::vec3::Vec3;
+
+use super::json::{self, Fields, Json, JsonError};
+use super::{load_obj, load_ply, LoadError};
+
+/// Reads a JSON scene file: `camera`, `render` settings, `background`, named `materials`, `objects` and `lights`.
+/// Paths of meshes are relative to the scene file. See `scenes/final.json` for an example.
+///
+/// ```no_run
+/// use rustracer::loaders::load_scene;
+///
+/// # fn main() -> rustracer::Result<()> {
+/// let desc = load_scene("scenes/final.json")?;
+/// let integrator = desc.settings.integrator.build(desc.settings.max_depth);
+/// let fb = desc.camera.build()?.render(&desc.scene, integrator.as_ref());
+/// rustracer::image::save(&fb, &desc.settings.output, &desc.settings.tone_mapping, &desc.settings.exr)?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn load_scene(path: impl AsRef<Path>) -> crate::Result<SceneDescription> {
+    load_scene_with_seed(path, None)
+}
+
+/// Same as [`load_scene`], with `seed` in place of the camera seed of the file when set,
+/// which the noise textures are also seeded from.
+pub fn load_scene_with_seed(path: impl AsRef<Path>, seed: Option<u64>) -> crate::Result<SceneDescription> {
+    let path: &Path = path.as_ref();
+    let scene_error = |line: Option<usize>, message: String| crate::Error::Scene(LoadError::new(path, line, message));
+    let text: String = fs::read_to_string(path).map_err(|e| scene_error(None, e.to_string()))?;
+    let root: Json = json::parse(&text).map_err(|e| scene_error(Some(e.line), e.message))?;
+
+    let mut loader = SceneLoader {
+        dir: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+        seed: 0,
+        materials: HashMap::new(),
+        objects: Objects::new(),
+    };
+    loader.load(&root, seed).map_err(|e| match e {
+        Error::Json(e) => scene_error(Some(e.line), e.message),
+        Error::Asset(e) => crate::Error::Asset(e),
+    })
+}
+
+/// Errors are either in the scene file itself or in a mesh it points to.
+enum Error {
+    Json(JsonError),
+    Asset(LoadError),
+}
+
+impl From<JsonError> for Error {
+    fn from(e: JsonError) -> Self {
+        Error::Json(e)
+    }
+}
+
+struct SceneLoader {
+    dir: PathBuf,
+    /// The camera seed, which also seeds the noise textures.
+    seed: u64,
+    materials: HashMap<String, Arc<dyn Material>>,
+    objects: Objects,
+}
+
+impl SceneLoader {
+    /// Reads the whole scene, with `seed` in place of the camera seed when set.
+    fn load(&mut self, root: &Json, seed: Option<u64>) -> Result<SceneDescription, Error> {
+        let fields: Fields = root.as_object()?;
+
+        let mut camera: CameraConfig = match fields.get("camera") {
+            Some(json) => camera(json)?,
+            None => CameraConfig::default(),
+        };
+        if let Some(seed) = seed { camera.seed = seed }
+        self.seed = camera.seed;
+        let settings: RenderSettings = match fields.get("render") {
+            Some(json) => render_settings(json)?,
+            None => RenderSettings::default(),
+        };
+        let background: Background = match fields.get("background") {
+            Some(json) => background(json)?,
+            None => Background::Sky,
+        };
+
+        // Materials first, so that objects can refer to them in any order.
+        if let Some(json) = fields.get("materials") {
+            for (name, json) in json.as_object()?.members() {
+                let mat: Arc<dyn Material> = material(json, &self.dir, self.seed)?;
+                self.materials.insert(name.clone(), mat);
+            }
+        }
+        if let Some(json) = fields.get("objects") {
+            for json in json.as_array()? {
+                self.object(json, false)?;
+            }
+        }
+        if let Some(json) = fields.get("lights") {
+            for json in json.as_array()? {
+                self.object(json, true)?;
+            }
+        }
+        fields.finish()?;
+
+        let objects: Objects = std::mem::take(&mut self.objects);
+        let mut scene: Scene = Scene::new(objects);
+        scene.background = background;
+        Ok(SceneDescription { scene, camera, settings })
+    }
+
+    /// Reads an object into the scene. Lights have an `emit` color instead of a material.
+    fn object(&mut self, json: &Json, light: bool) -> Result<(), Error> {
+        let fields: Fields = json.as_object()?;
+        let kind: &str = fields.required("type")?.as_str()?;
+
+        let mat = || -> Result<Arc<dyn Material>, Error> {
+            if light {
+                let emit: Arc<dyn Texture> = texture(fields.required("emit")?, &self.dir, self.seed)?;
+                return Ok(Arc::new(DiffuseLight::from_texture(emit)));
+            }
+            let json: &Json = fields.required("material")?;
+            match &json.value {
+                json::Value::String(name) => self.materials.get(name).cloned()
+                    .ok_or_else(|| JsonError::new(json.line, format!("unknown material \"{}\"", name)).into()),
+                _ => material(json, &self.dir, self.seed),
+            }
+        };
+        let invalid = |e: crate::Error| -> Error { JsonError::new(json.line, e.to_string()).into() };
+
+        match kind {
+            "sphere" => {
+                let center: Vec3 = fields.required("center")?.as_vec3()?;
+                let radius: f64 = positive(fields.required("radius")?)?;
+                self.objects.add(Sphere::new(center, radius, mat()?));
+            }
+            "triangle" => {
+                let [p0, p1, p2] = vec3s(fields.required("vertices")?)?;
+                let mut triangle = Triangle::new(p0, p1, p2, mat()?);
+                if let Some(json) = fields.get("normals") {
+                    let [n0, n1, n2] = vec3s(json)?;
+                    triangle = triangle.with_normals(n0, n1, n2);
+                }
+                if let Some(json) = fields.get("uvs") {
+                    let uvs: Vec<(f64, f64)> = json.as_array()?.iter().map(|uv| match uv.as_array()? {
+                        [u, v] => Ok((u.as_f64()?, v.as_f64()?)),
+                        _ => Err(JsonError::new(uv.line, "expected an array of 2 numbers")),
+                    }).collect::<Result<_, _>>()?;
+                    let [uv0, uv1, uv2] = uvs[..] else { return Err(JsonError::new(json.line, "expected 3 texture coordinates").into()) };
+                    triangle = triangle.with_uvs(uv0, uv1, uv2);
+                }
+                self.objects.add(triangle);
+            }
+            "quad" => {
+                let corner: Vec3 = fields.required("corner")?.as_vec3()?;
+                let (u, v) = (fields.required("u")?.as_vec3()?, fields.required("v")?.as_vec3()?);
+                self.objects.add(Quad::new(corner, u, v, mat()?).map_err(invalid)?);
+            }
+            "disk" => {
+                let center: Vec3 = fields.required("center")?.as_vec3()?;
+                let (normal, radius) = (fields.required("normal")?.as_vec3()?, fields.required("radius")?.as_f64()?);
+                self.objects.add(Disk::new(center, normal, radius, mat()?).map_err(invalid)?);
+            }
+            "box" => {
+                let (min, max) = (fields.required("min")?.as_vec3()?, fields.required("max")?.as_vec3()?);
+                self.objects.add(Cuboid::new(min, max, mat()?).map_err(invalid)?);
+            }
+            "ply" => {
+                let path: PathBuf = self.dir.join(fields.required("path")?.as_str()?);
+                let smooth: bool = fields.get("smooth_normals").map(Json::as_bool).transpose()?.unwrap_or(false);
+                let mut mesh = load_ply(&path, mat()?).map_err(Error::Asset)?;
+                if smooth && mesh.normals().is_empty() {
+                    mesh = mesh.with_smooth_normals();
+                }
+                self.objects.add(mesh);
+            }
+            // Materials come from the MTL files.
+            "obj" if !light => {
+                let path: PathBuf = self.dir.join(fields.required("path")?.as_str()?);
+                for mesh in load_obj(&path).map_err(Error::Asset)? {
+                    self.objects.add(mesh);
+                }
+            }
+            _ => return Err(JsonError::new(json.line, format!("unknown {} type \"{}\"", if light { "light" } else { "object" }, kind)).into()),
+        }
+        fields.finish()?;
+        Ok(())
+    }
+}
+
+fn camera(json: &Json) -> Result<CameraConfig, JsonError> {
+    let fields: Fields = json.as_object()?;
+    let mut cam: CameraConfig = CameraConfig::default();
+
+    if let Some(json) = fields.get("aspect") { cam.aspect = positive(json)? }
+    if let Some(json) = fields.get("image_width") { cam.image_width = at_least_one(json)? }
+    if let Some(json) = fields.get("samples_per_pixel") { cam.samples_per_pixel = at_least_one(json)? }
+    if let Some(json) = fields.get("lookfrom") { cam.lookfrom = json.as_vec3()? }
+    if let Some(json) = fields.get("lookat") { cam.lookat = json.as_vec3()? }
+    if let Some(json) = fields.get("vup") { cam.vup = json.as_vec3()? }
+    if let Some(json) = fields.get("vfov") { cam.vfov = json.as_f64()? }
+    if let Some(json) = fields.get("defocus_angle") { cam.defocus_angle = json.as_f64()? }
+    if let Some(json) = fields.get("focus_dist") { cam.focus_dist = positive(json)? }
+    if let Some(json) = fields.get("threads") { cam.threads = json.as_u64()? as usize }
+    if let Some(json) = fields.get("tile_size") { cam.tile_size = at_least_one(json)? }
+    if let Some(json) = fields.get("seed") { cam.seed = json.as_u64()? }
+    fields.finish()?;
+
+    cam.validate().map_err(|e| JsonError::new(fields.line, e.to_string()))?;
+    Ok(cam)
+}
+
+fn render_settings(json: &Json) -> Result<RenderSettings, JsonError> {
+    let fields: Fields = json.as_object()?;
+    let mut settings: RenderSettings = RenderSettings::default();
+
+    if let Some(json) = fields.get("integrator") {
+        let name: &str = json.as_str()?;
+        settings.integrator = IntegratorKind::from_name(name).ok_or_else(|| JsonError::new(
+            json.line,
+            format!("unknown integrator \"{}\", expected one of {}", name, IntegratorKind::NAMES.join(", ")),
+        ))?;
+    }
+    if let Some(json) = fields.get("max_depth") { settings.max_depth = json.as_u32()? }
+    if let Some(json) = fields.get("output") { settings.output = PathBuf::from(json.as_str()?) }
+
+    let exposure: f64 = fields.get("exposure").map(Json::as_f64).transpose()?.unwrap_or(0.);
+    let white: Option<&Json> = fields.get("white");
+    let operator: ToneMap = match fields.get("tone_map") {
+        None => ToneMap::Clamp,
+        Some(json) => match json.as_str()? {
+            "clamp" => ToneMap::Clamp,
+            "reinhard" => ToneMap::Reinhard,
+            "reinhard_extended" => ToneMap::ReinhardExtended { white: white.map(positive).transpose()?.unwrap_or(4.) },
+            "hable" => ToneMap::Hable,
+            "aces" => ToneMap::Aces,
+            name => return Err(JsonError::new(
+                json.line,
+                format!("unknown tone map \"{}\", expected one of clamp, reinhard, reinhard_extended, hable, aces", name),
+            )),
+        },
+    };
+    settings.tone_mapping = ToneMapping::new(exposure, operator);
+
+    if let Some(json) = fields.get("exr_pixel_type") {
+        let name: &str = json.as_str()?;
+        settings.exr.pixel_type = ExrPixelType::from_name(name).ok_or_else(|| JsonError::new(
+            json.line,
+            format!("unknown EXR pixel type \"{}\", expected one of {}", name, ExrPixelType::NAMES.join(", ")),
+        ))?;
+    }
+    if let Some(json) = fields.get("exr_compression") {
+        let name: &str = json.as_str()?;
+        settings.exr.compression = ExrCompression::from_name(name).ok_or_else(|| JsonError::new(
+            json.line,
+            format!("unknown EXR compression \"{}\", expected one of {}", name, ExrCompression::NAMES.join(", ")),
+        ))?;
+    }
+
+    if let Some(json) = fields.get("denoise") {
+        settings.denoiser = json.as_bool()?.then(Denoiser::default);
+    }
+    if let Some(json) = fields.get("aovs") { settings.aovs = json.as_bool()? }
+    fields.finish()?;
+    Ok(settings)
+}
+
+fn background(json: &Json) -> Result<Background, JsonError> {
+    match &json.value {
+        json::Value::String(s) if s == "sky" => Ok(Background::Sky),
+        json::Value::Array(_) => Ok(Background::Solid(non_negative(json)?)),
+        _ => Err(JsonError::new(json.line, "expected \"sky\" or a color")),
+    }
+}
+
+/// Reads a material. Image paths are relative to `dir`, noise textures are seeded from `seed`.
+fn material(json: &Json, dir: &Path, seed: u64) -> Result<Arc<dyn Material>, Error> {
+    let fields: Fields = json.as_object()?;
+    let kind: &Json = fields.required("type")?;
+    let mat: Arc<dyn Material> = match kind.as_str()? {
+        "lambertian" => Arc::new(Lambertian::from_texture(texture(fields.required("albedo")?, dir, seed)?)),
+        "metal" => {
+            let albedo: Arc<dyn Texture> = texture(fields.required("albedo")?, dir, seed)?;
+            let fuzz: f64 = fields.get("fuzz").map(Json::as_f64).transpose()?.unwrap_or(0.);
+            Arc::new(Metal::from_texture(albedo, fuzz.clamp(0., 1.)))
+        }
+        "dielectric" => Arc::new(Dielectric::new(positive(fields.required("ior")?)?)),
+        "diffuse_light" => Arc::new(DiffuseLight::from_texture(texture(fields.required("emit")?, dir, seed)?)),
+        name => return Err(JsonError::new(
+            kind.line,
+            format!("unknown material type \"{}\", expected one of lambertian, metal, dielectric, diffuse_light", name),
+        ).into()),
+    };
+    fields.finish()?;
+    Ok(mat)
+}
+
+/// Reads a color, or a texture object wherever a color is expected.
+fn texture(json: &Json, dir: &Path, seed: u64) -> Result<Arc<dyn Texture>, Error> {
+    if let json::Value::Array(_) = json.value {
+        return Ok(Arc::new(SolidColor::new(non_negative(json)?)));
+    }
+    let fields: Fields = json.as_object()?;
+    let kind: &Json = fields.required("type")?;
+    let tex: Arc<dyn Texture> = match kind.as_str()? {
+        "checker" => {
+            let scale: f64 = positive(fields.required("scale")?)?;
+            Arc::new(Checker::new(scale, texture(fields.required("even")?, dir, seed)?, texture(fields.required("odd")?, dir, seed)?))
+        }
+        "image" => {
+            let path: PathBuf = dir.join(fields.required("path")?.as_str()?);
+            let mut image: ImageTexture = ImageTexture::open(&path).map_err(Error::Asset)?;
+            if let Some(json) = fields.get("wrap") {
+                image = image.with_wrap(match json.as_str()? {
+                    "repeat" => Wrap::Repeat,
+                    "clamp" => Wrap::Clamp,
+                    "mirror" => Wrap::Mirror,
+                    name => return Err(JsonError::new(json.line, format!("unknown wrap mode \"{}\", expected one of repeat, clamp, mirror", name)).into()),
+                });
+            }
+            if let Some(json) = fields.get("filter") {
+                image = image.with_filter(match json.as_str()? {
+                    "nearest" => Filter::Nearest,
+                    "bilinear" => Filter::Bilinear,
+                    "trilinear" => Filter::Trilinear,
+                    name => return Err(JsonError::new(json.line, format!("unknown filter \"{}\", expected one of nearest, bilinear, trilinear", name)).into()),
+                });
+            }
+            Arc::new(image)
+        }
+        "fbm" | "turbulence" | "marble" | "wood" | "worley" => {
+            let pattern: NoisePattern = match kind.as_str()? {
+                "fbm" => NoisePattern::Fbm,
+                "turbulence" => NoisePattern::Turbulence,
+                "marble" => NoisePattern::Marble,
+                "wood" => NoisePattern::Wood,
+                _ => NoisePattern::Worley,
+            };
+            // Textures with the same pattern would look alike without a seed of their own.
+            let offset: u64 = fields.get("seed").map(Json::as_u64).transpose()?.unwrap_or(0);
+            let mut noise = NoiseTexture::new(pattern, seed.wrapping_add(offset));
+            if let Some(json) = fields.get("scale") { noise = noise.with_scale(positive(json)?) }
+            if let Some(json) = fields.get("octaves") { noise = noise.with_octaves(at_least_one(json)?) }
+            if let Some(json) = fields.get("colors") {
+                let [low, high] = match json.as_array()? {
+                    [low, high] => [non_negative(low)?, non_negative(high)?],
+                    items => return Err(JsonError::new(json.line, format!("expected 2 colors, found {}", items.len())).into()),
+                };
+                noise = noise.with_colors(low, high);
+            }
+            Arc::new(noise)
+        }
+        name => return Err(JsonError::new(
+            kind.line,
+            format!("unknown texture type \"{}\", expected one of checker, image, fbm, turbulence, marble, wood, worley", name),
+        ).into()),
+    };
+    fields.finish()?;
+    Ok(tex)
+}
+
+// ------------------------- Checked values
+
+fn positive(json: &Json) -> Result<f64, JsonError> {
+    let v: f64 = json.as_f64()?;
+    if v > 0. { Ok(v) } else { Err(JsonError::new(json.line, format!("expected a positive number, found {}", v))) }
+}
+
+fn at_least_one(json: &Json) -> Result<u32, JsonError> {
+    match json.as_u32()? {
+        0 => Err(JsonError::new(json.line, "expected at least 1")),
+        v => Ok(v),
+    }
+}
+
+/// A color, which can't have negative components.
+fn non_negative(json: &Json) -> Result<Vec3, JsonError> {
+    let v: Vec3 = json.as_vec3()?;
+    if v.x() < 0. || v.y() < 0. || v.z() < 0. {
+        return Err(JsonError::new(json.line, "expected non-negative components"));
+    }
+    Ok(v)
+}
+
+fn vec3s(json: &Json) -> Result<[Vec3; 3], JsonError> {
+    match json.as_array()? {
+        [a, b, c] => Ok([a.as_vec3()?, b.as_vec3()?, c.as_vec3()?]),
+        items => Err(JsonError::new(json.line, format!("expected 3 points, found {}", items.len()))),
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn scene_file_errors() {
+    use crate::image::ExrOptions;
+
+    let dir: PathBuf = std::env::temp_dir().join("rustracer_scene_test");
+    fs::create_dir_all(&dir).unwrap();
+    let load = |name: &str, text: &str| {
+        let path: PathBuf = dir.join(name);
+        fs::write(&path, text).unwrap();
+        load_scene(&path)
+    };
+
+    let desc = load("ok.json", r#"{
+        "camera": { "image_width": 64, "lookfrom": [0, 0, 5] },
+        "render": { "integrator": "direct", "tone_map": "aces", "denoise": true, "aovs": true, "exr_pixel_type": "float", "exr_compression": "none" },
+        "background": [0, 0, 0],
+        "materials": {
+            "red": { "type": "lambertian", "albedo": [0.8, 0.1, 0.1] },
+            "floor": { "type": "lambertian", "albedo": { "type": "checker", "scale": 0.5, "even": [0, 0, 0], "odd": [1, 1, 1] } },
+            "stone": { "type": "lambertian", "albedo": { "type": "marble", "scale": 4, "octaves": 5, "colors": [[0.2, 0.2, 0.2], [0.9, 0.9, 0.9]] } }
+        },
+        "objects": [
+            { "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": "red" },
+            { "type": "sphere", "center": [2, 0, 0], "radius": 0.5, "material": { "type": "metal", "albedo": [1, 1, 1] } },
+            { "type": "box", "min": [-3, -1, -1], "max": [-2, 1, 0], "material": "stone" },
+            { "type": "disk", "center": [0, -1, 0], "normal": [0, 1, 0], "radius": 5, "material": "floor" }
+        ],
+        "lights": [
+            { "type": "sphere", "center": [0, 3, 0], "radius": 0.5, "emit": [4, 4, 4] },
+            { "type": "quad", "corner": [-1, 4, -1], "u": [2, 0, 0], "v": [0, 0, 2], "emit": [2, 2, 2] }
+        ]
+    }"#).unwrap();
+    assert_eq!(desc.camera.image_width, 64);
+    assert_eq!(desc.settings.integrator, IntegratorKind::Direct);
+    assert!(desc.settings.denoiser.is_some() && desc.settings.aovs);
+    assert_eq!(desc.settings.exr, ExrOptions { pixel_type: ExrPixelType::Float, compression: ExrCompression::None });
+    assert_eq!(desc.scene.lights.len(), 2);
+    assert_eq!(desc.scene.background, Background::Solid(Vec3::new(0., 0., 0.)));
+
+    let error = |text: &str| load("bad.json", text).err().unwrap().to_string();
+    assert!(error("{\n\"objects\": [{ \"type\": \"sphere\", \"center\": [0, 0, 0], \"radius\": 1, \"material\": \"nope\" }]}")
+        .ends_with("bad.json:2: unknown material \"nope\""));
+    assert!(error("{\n\"camera\": {\n\"fov\": 40 }}").ends_with("bad.json:3: unknown member \"fov\""));
+    assert!(error("{\n\"camera\": { \"vfov\": 200 }}").ends_with("bad.json:2: invalid parameter: the vertical field of view must be between 0 and 180 degrees"));
+    assert!(error("{\n\"camera\": { \"defocus_angle\": -1 }}").ends_with("bad.json:2: invalid parameter: the defocus angle can't be negative"));
+    assert!(error("{\"objects\": [\n{ \"type\": \"sphere\", \"center\": [0, 0], \"radius\": 1 }]}").ends_with("bad.json:2: expected an array of 3 numbers, found 2 items"));
+    assert!(error("{\"objects\": [\n{ \"type\": \"quad\", \"corner\": [0, 0, 0], \"u\": [1, 0, 0], \"v\": [2, 0, 0], \"material\": { \"type\": \"lambertian\", \"albedo\": [1, 0, 0] } }]}")
+        .contains("bad.json:2: invalid parameter: the edges of a quad must not be zero or parallel"));
+    assert!(error("{\"objects\": [\n{ \"type\": \"box\", \"min\": [0, 0, 0], \"max\": [1, 0, 1], \"material\": { \"type\": \"lambertian\", \"albedo\": [1, 0, 0] } }]}")
+        .contains("bad.json:2: invalid parameter: the corners of a box must differ along every axis"));
+    assert!(error("{\"render\": {\n\"integrator\": \"bdpt\"}}").contains("bad.json:2: unknown integrator \"bdpt\""));
+    assert!(error("{\"render\": {\n\"exr_compression\": \"piz\"}}").contains("bad.json:2: unknown EXR compression \"piz\""));
+    assert!(error("{\"materials\": { \"wood\": { \"type\": \"lambertian\",\n\"albedo\": { \"type\": \"noise\" } } }}").contains("bad.json:2: unknown texture type \"noise\""));
+    assert!(error("{\"materials\": { \"wood\": { \"type\": \"lambertian\",\n\"albedo\": { \"type\": \"wood\", \"colors\": [[1, 1, 1]] } } }}").contains("bad.json:2: expected 2 colors, found 1"));
+    assert!(error("{\"materials\": { \"wood\": { \"type\": \"lambertian\", \"albedo\": { \"type\": \"image\", \"path\": \"missing.ppm\" } } }}").contains("missing.ppm"));
+    assert!(error("{\"objects\": [\n{ \"type\": \"ply\", \"path\": \"missing.ply\", \"material\": { \"type\": \"dielectric\", \"ior\": 1.5 } }]}").contains("missing.ply"));
+}
+#[test]
+fn seed_override() {
+    use crate::objects::{HitRecord, Object};
+    use crate::ray::Ray;
+    use crate::utils::Interval;
+    use crate::vec3::{Color3, Point3};
+
+    let path: PathBuf = std::env::temp_dir().join("rustracer_seed_test.json");
+    fs::write(&path, r#"{
+        "camera": { "seed": 7 },
+        "objects": [{ "type": "sphere", "center": [0, 0, 0], "radius": 1, "material": { "type": "lambertian", "albedo": { "type": "fbm", "scale": 3 } } }]
+    }"#).unwrap();
+
+    // The albedo along a line across the sphere.
+    let albedos = |seed: Option<u64>| -> Vec<Color3> {
+        let desc: SceneDescription = load_scene_with_seed(&path, seed).unwrap();
+        (0..20).map(|i| {
+            let mut rec: HitRecord = HitRecord::new();
+            let r = Ray::new(Point3::new(-0.9 + 0.09 * i as f64, 0.1, 5.), Vec3::new(0., 0., -1.));
+            assert!(desc.scene.world.hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec));
+            rec.mat.albedo(&rec)
+        }).collect()
+    };
+    assert_eq!(load_scene_with_seed(&path, Some(8)).unwrap().camera.seed, 8);
+    assert_eq!(albedos(None), albedos(Some(7)));
+    assert_ne!(albedos(None), albedos(Some(8)));
+}
@@ -1,80 +1,62 @@
-mod camera;
-mod materials;
-mod objects;
-mod ray;
-mod utils;
-mod vec3;
+mod cli;
 
 
-use std::rc::Rc;
+use std::env;
+use std::io::{self, Write};
+use std::process;
 
-use fastrand::f64;
-use materials::{Dielectric, Lambertian, Metal};
-use vec3::{Color3, Point3, Vec3};
-use objects::{Objects, Sphere};
-use camera::Camera;
+use rustracer::integrators::Integrator;
+use rustracer::image::{self, Aovs, Framebuffer};
+use rustracer::{loaders, Camera, Error, SceneDescription};
 
+use cli::{Args, Command, USAGE};
 
-fn main() {
-    let mut objects: Objects = Objects::new();
-
-    let mat_ground = Rc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
-    let mut all_spheres: Vec<Sphere> = vec![
-        Sphere::new(Point3::new(0., -1000., 0.), 1000., mat_ground.clone())
-    ];
-
-    let avoid_center: Point3 = Point3::new(4.0, 0.2, 0.0);
-    
-    for a in -11..11 {
-        for b in -11..11 {
-            let choose_mat = f64();
-            let center = Point3::new(a as f64 + 0.9*f64(), 0.2, b as f64 + 0.9*f64());
-
-            if (center - avoid_center).len() > 0.9 {
-
-                if choose_mat < 0.6 { // Matte
-                    let albedo: Color3 = Color3::new(f64(), f64(), f64());
-                    let sphere_mat = Rc::new(Lambertian::new(albedo));
-                    all_spheres.push(Sphere::new(center, 0.2, sphere_mat));
-
-                } else if choose_mat < 0.9 { // Metal
-                    let albedo: Color3 = Color3::rand(0.5, 1.0);
-                    let fuzz: f64 = f64()/2.0;
-                    let sphere_mat = Rc::new(Metal::new(albedo, fuzz));
-                    all_spheres.push(Sphere::new(center, 0.2, sphere_mat));
-
-                } else { // Glass
-                    let sphere_mat = Rc::new(Dielectric::new(1.3 + 0.4 * f64()));
-                    all_spheres.push(Sphere::new(center, 0.2, sphere_mat));
 
-                }
-            }
+fn main() {
+    let args = match Command::parse(env::args().skip(1)) {
+        Ok(Command::Render(args)) => args,
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\nTry 'rustracer --help' for more information.", e);
+            process::exit(2);
         }
+    };
+
+    if let Err(e) = run(&args) {
+        eprintln!("error: {}", e);
+        process::exit(match e {
+            Error::InvalidParameter(_) => 2,
+            _ => 1,
+        });
     }
-    
-    let mat1 = Rc::new(Dielectric::new(1.5));
-    all_spheres.push(Sphere::new(Point3::new(0.0, 1.0, 0.0), 1.0, mat1));
-    let mat2 = Rc::new(Lambertian::new(Color3::new(0.4, 0.2, 0.1)));
-    all_spheres.push(Sphere::new(Point3::new(-4.0, 1.0, 0.0), 1.0, mat2));
-    let mat3 = Rc::new(Metal::new(Color3::new(0.7, 0.6, 0.5), 0.0));
-    all_spheres.push(Sphere::new(Point3::new(4.0, 1.0, 0.0), 1.0, mat3));
+}
 
-    for obj in all_spheres.iter() {
-        objects.add(obj);
+fn run(args: &Args) -> rustracer::Result<()> {
+    let mut desc: SceneDescription = loaders::load_scene_with_seed(&args.scene, args.seed())?;
+    args.apply(&mut desc).map_err(Error::InvalidParameter)?;
+    let SceneDescription { scene, camera, settings } = desc;
+    let camera: Camera = camera.build()?;
+    println!("{}", scene.world.stats());
+
+    let integrator: Box<dyn Integrator> = settings.integrator.build(settings.max_depth);
+    // The denoiser needs the output variables too.
+    let mut aovs: Option<Aovs> = (settings.denoiser.is_some() || settings.aovs)
+        .then(|| Aovs::new(camera.image_width(), camera.image_height()));
+    let mut fb: Framebuffer = camera.render_with_progress(&scene, integrator.as_ref(), aovs.as_mut(), &|done, total| {
+        print!("\rTiles: {}/{}", done, total);
+        // Progress is best effort.
+        let _ = io::stdout().flush();
+    });
+    println!();
+
+    if let (Some(denoiser), Some(aovs)) = (settings.denoiser, &aovs) {
+        fb = denoiser.denoise(&fb, aovs);
+    }
+    match &aovs {
+        Some(aovs) if settings.aovs => image::save_layers(&fb, aovs, &settings.output, &settings.tone_mapping, &settings.exr),
+        _ => image::save(&fb, &settings.output, &settings.tone_mapping, &settings.exr),
     }
-
-
-    let mut cam: Camera = Camera::default();
-    cam.aspect = 16.0 / 9.0;
-    cam.image_width = 720;
-    cam.samples_per_pixel = 1;
-    cam.max_depth = 3;
-    cam.vfov = 20.0;
-    cam.lookfrom = Point3::new(13.0, 2.0, 3.0);
-    cam.lookat = Point3::new(0.0, 0.0, 0.0);
-    cam.vup = Vec3::new(0.0, 1.0, 0.0);
-    cam.defocus_angle = 0.6;
-    cam.focus_dist = 10.0;
-
-    cam.render(objects);
 }
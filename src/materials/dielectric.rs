@@ -2,7 +2,7 @@ use fastrand::f64;
 
 use crate::{objects::HitRecord, ray::Ray, vec3::{Color3, Vec3}};
 
-use super::Material;
+use super::{reflect_differentials, refract_differentials, Material};
 
 pub struct Dielectric {
     ir: f64,
@@ -34,15 +34,16 @@ impl Material for Dielectric {
         let sin_theta: f64 = (1.0 - cos_theta * cos_theta).sqrt();
 
         let cannot_refract: bool = refraction_ratio * sin_theta > 1.0;
-        let dir: Vec3;
-
-        if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > f64() {
-            dir = unit_dir | rec.normal;
+        let (dir, differentials) = if cannot_refract || Self::reflectance(cos_theta, refraction_ratio) > f64() {
+            (unit_dir | rec.normal, reflect_differentials(r_in, rec))
         } else {
-            dir = unit_dir.refract(rec.normal, refraction_ratio);
-        }
+            (unit_dir.refract(rec.normal, refraction_ratio), refract_differentials(r_in, rec, refraction_ratio))
+        };
 
         *r_scattered = Ray::new(rec.p, dir);
+        if let Some(differentials) = differentials {
+            *r_scattered = r_scattered.with_differentials(differentials);
+        }
         true
     }
 }
\ No newline at end of file
@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{objects::HitRecord, ray::Ray, textures::{SolidColor, Texture}, vec3::Color3};
+
+use super::Material;
+
+/// Emits light evenly from both sides of the surface, and doesn't scatter any.
+pub struct DiffuseLight {
+    emit: Arc<dyn Texture>,
+}
+
+impl DiffuseLight {
+    pub fn new(emit: Color3) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(emit)))
+    }
+
+    pub fn from_texture(emit: Arc<dyn Texture>) -> Self {
+        Self {
+            emit,
+        }
+    }
+}
+
+impl Material for DiffuseLight {
+    fn scatter(&self, _r_in: &Ray, _rec: &HitRecord, _attenuation: &mut Color3, _r_scattered: &mut Ray) -> bool {
+        false
+    }
+
+    fn emitted(&self, rec: &HitRecord) -> Color3 {
+        self.emit.value_lod(rec.u, rec.v, rec.p, rec.footprint)
+    }
+
+    fn is_emissive(&self) -> bool {
+        true
+    }
+}
@@ -1,13 +1,21 @@
-use crate::{objects::HitRecord, ray::Ray, vec3::{Color3, Vec3}};
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use crate::{objects::HitRecord, ray::Ray, textures::{SolidColor, Texture}, vec3::{Color3, Vec3}};
 
 use super::Material;
 
+/// Diffuse material. Its albedo is tinted by the vertex colors of the meshes having some.
 pub struct Lambertian {
-    albedo: Color3,
+    albedo: Arc<dyn Texture>,
 }
 
 impl Lambertian {
     pub fn new(albedo: Color3) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)))
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>) -> Self {
         Self {
             albedo,
         }
@@ -23,7 +31,21 @@ impl Material for Lambertian {
         }
 
         *r_scattered = Ray::new(rec.p, scatter_dir);
-        *attenuation = self.albedo;
+        *attenuation = self.albedo(rec);
         true
     }
+
+    fn eval(&self, _r_in: &Ray, rec: &HitRecord, dir: Vec3) -> Option<(Color3, f64)> {
+        // `scatter` is cosine-weighted, so both are albedo * cos / pi.
+        let pdf: f64 = (rec.normal * dir.unit()).max(0.) / PI;
+        Some((self.albedo(rec) * pdf, pdf))
+    }
+
+    fn albedo(&self, rec: &HitRecord) -> Color3 {
+        let albedo: Color3 = self.albedo.value_lod(rec.u, rec.v, rec.p, rec.footprint);
+        match rec.vertex_color {
+            Some(color) => albedo.mult(color),
+            None => albedo,
+        }
+    }
 }
\ No newline at end of file
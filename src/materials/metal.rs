@@ -1,14 +1,20 @@
-use crate::{objects::HitRecord, ray::Ray, vec3::{Color3, Vec3}};
+use std::sync::Arc;
 
-use super::Material;
+use crate::{objects::HitRecord, ray::Ray, textures::{SolidColor, Texture}, vec3::{Color3, Vec3}};
+
+use super::{reflect_differentials, Material};
 
 pub struct Metal {
-    albedo: Color3,
+    albedo: Arc<dyn Texture>,
     fuzz: f64,
 }
 
 impl Metal {
     pub fn new(albedo: Color3, fuzz: f64) -> Self {
+        Self::from_texture(Arc::new(SolidColor::new(albedo)), fuzz)
+    }
+
+    pub fn from_texture(albedo: Arc<dyn Texture>, fuzz: f64) -> Self {
         Self {
             albedo,
             fuzz,
@@ -21,7 +27,15 @@ impl Material for Metal {
         let reflection_dir: Vec3 = r_in.dir().unit() | rec.normal;
 
         *r_scattered = Ray::new(rec.p, reflection_dir + self.fuzz * Vec3::rand_unit());
-        *attenuation = self.albedo;
+        // Fuzz is left out: it blurs the reflection anyway.
+        if let Some(differentials) = reflect_differentials(r_in, rec) {
+            *r_scattered = r_scattered.with_differentials(differentials);
+        }
+        *attenuation = self.albedo(rec);
         r_scattered.dir() * rec.normal > 0.
     }
+
+    fn albedo(&self, rec: &HitRecord) -> Color3 {
+        self.albedo.value_lod(rec.u, rec.v, rec.p, rec.footprint)
+    }
 }
\ No newline at end of file
@@ -1,4 +1,4 @@
-use crate::{objects::HitRecord, ray::Ray, vec3::Color3};
+use crate::{objects::HitRecord, ray::{Differentials, Ray}, vec3::{Color3, Vec3}};
 
 mod lambertian;
 pub use lambertian::*;
@@ -6,8 +6,121 @@ mod metal;
 pub use metal::*;
 mod dielectric;
 pub use dielectric::*;
+mod diffuse_light;
+pub use diffuse_light::*;
 
-pub trait Material {
+pub trait Material: Send + Sync {
     /// Returns whether a ray is scattered. If it is, modifies `attenuation` and `r_scattered` for the material.
     fn scatter(&self, r_in: &Ray, rec: &HitRecord, attenuation: &mut Color3, r_scattered: &mut Ray) -> bool;
-}
\ No newline at end of file
+    /// Returns the light emitted at the hit point. Most materials don't emit any.
+    fn emitted(&self, _rec: &HitRecord) -> Color3 {
+        Color3::new(0., 0., 0.)
+    }
+    /// Whether `emitted` can return something other than black, so that objects made of it are sampled as lights.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    /// Returns the BSDF times the cosine term toward `dir`, along with the density with which `scatter` picks `dir`.
+    /// Returns `None` for specular materials, toward which lights can't be sampled.
+    fn eval(&self, _r_in: &Ray, _rec: &HitRecord, _dir: Vec3) -> Option<(Color3, f64)> {
+        None
+    }
+    /// Returns the base color at the hit point, written to the albedo output. Defaults to white.
+    fn albedo(&self, _rec: &HitRecord) -> Color3 {
+        Color3::new(1., 1., 1.)
+    }
+}
+
+/// Differentials of the mirror reflection of `r_in` at `rec`, for rays having some.
+pub(crate) fn reflect_differentials(r_in: &Ray, rec: &HitRecord) -> Option<Differentials> {
+    let differentials: &Differentials = r_in.differentials()?;
+    let (d, n) = (r_in.dir().unit(), rec.normal);
+    // Derivative of d - 2 (d.n) n
+    let reflect = |dir: Vec3, dn: Vec3| -> Vec3 {
+        let dd: Vec3 = dir.unit() - d;
+        (d | n) + dd - 2. * ((dd * n + d * dn) * n + (d * n) * dn)
+    };
+    Some(Differentials {
+        rx_origin: rec.p + rec.dpdx,
+        rx_dir: reflect(differentials.rx_dir, rec.dndx),
+        ry_origin: rec.p + rec.dpdy,
+        ry_dir: reflect(differentials.ry_dir, rec.dndy),
+    })
+}
+
+/// Differentials of the refraction of `r_in` at `rec`, `ratio` being the index of refraction
+/// on the side of `r_in` over the one on the other side.
+pub(crate) fn refract_differentials(r_in: &Ray, rec: &HitRecord, ratio: f64) -> Option<Differentials> {
+    let differentials: &Differentials = r_in.differentials()?;
+    let (d, n) = (r_in.dir().unit(), rec.normal);
+    let cos_in: f64 = -(d * n);
+    let cos_out_squared: f64 = 1. - ratio * ratio * (1. - cos_in * cos_in);
+    if cos_out_squared <= 0. {
+        return None;
+    }
+    let cos_out: f64 = cos_out_squared.sqrt();
+    let refracted: Vec3 = d.refract(n, ratio);
+    // Derivative of ratio d + (ratio cos_in - cos_out) n
+    let refract = |dir: Vec3, dn: Vec3| -> Vec3 {
+        let dd: Vec3 = dir.unit() - d;
+        let d_cos_in: f64 = -(dd * n + d * dn);
+        let d_mu: f64 = (ratio - ratio * ratio * cos_in / cos_out) * d_cos_in;
+        refracted + ratio * dd + (ratio * cos_in - cos_out) * dn + d_mu * n
+    };
+    Some(Differentials {
+        rx_origin: rec.p + rec.dpdx,
+        rx_dir: refract(differentials.rx_dir, rec.dndx),
+        ry_origin: rec.p + rec.dpdy,
+        ry_dir: refract(differentials.ry_dir, rec.dndy),
+    })
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn differentials_follow_offset_rays() {
+    use std::sync::Arc;
+
+    use crate::objects::{Object, Sphere};
+    use crate::utils::Interval;
+    use crate::vec3::Point3;
+
+    let sphere = Sphere::new(Point3::new(0., 0., 0.), 1., Arc::new(Lambertian::new(Color3::new(1., 1., 1.))));
+    let hit = |r: &Ray| {
+        let mut rec: HitRecord = HitRecord::new();
+        assert!(sphere.hit(r, &Interval::new(0.001, f64::INFINITY), &mut rec));
+        rec
+    };
+
+    let (orig, dir) = (Point3::new(0.3, 0.2, 5.), Vec3::new(0., 0., -1.));
+    let (offset_x, offset_y) = (Vec3::new(1e-3, 0., 0.), Vec3::new(0., 1e-3, 0.));
+    let r = Ray::new(orig, dir).with_differentials(Differentials { rx_origin: orig, rx_dir: dir + offset_x, ry_origin: orig, ry_dir: dir + offset_y });
+    let rec: HitRecord = hit(&r);
+    let (rec_x, rec_y) = (hit(&Ray::new(orig, dir + offset_x)), hit(&Ray::new(orig, dir + offset_y)));
+
+    // What the offset rays actually turn into, against what the differentials predict
+    let check = |predicted: Differentials, main: Vec3, exact_x: Vec3, exact_y: Vec3| {
+        for (origin, dir, exact_p, exact_dir) in [(predicted.rx_origin, predicted.rx_dir, rec_x.p, exact_x), (predicted.ry_origin, predicted.ry_dir, rec_y.p, exact_y)] {
+            let change: f64 = (exact_dir.unit() - main.unit()).len();
+            assert!(change > 1e-4);
+            assert!((dir.unit() - exact_dir.unit()).len() < 0.02 * change);
+            assert!((origin - exact_p).len() < 1e-5);
+        }
+    };
+    check(
+        reflect_differentials(&r, &rec).unwrap(),
+        dir | rec.normal,
+        (dir + offset_x).unit() | rec_x.normal,
+        (dir + offset_y).unit() | rec_y.normal,
+    );
+    check(
+        refract_differentials(&r, &rec, 1. / 1.5).unwrap(),
+        dir.refract(rec.normal, 1. / 1.5),
+        (dir + offset_x).unit().refract(rec_x.normal, 1. / 1.5),
+        (dir + offset_y).unit().refract(rec_y.normal, 1. / 1.5),
+    );
+
+    assert!(reflect_differentials(&Ray::new(orig, dir), &rec).is_none());
+}
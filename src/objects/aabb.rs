@@ -0,0 +1,93 @@
+use crate::ray::Ray;
+use crate::utils::Interval;
+use crate::vec3::{Point3, Vec3};
+
+/// Axis-aligned bounding box, as one interval per axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Aabb {
+    pub const EMPTY: Aabb = Aabb { x: Interval::EMPTY, y: Interval::EMPTY, z: Interval::EMPTY };
+
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Self { x, y, z }.pad()
+    }
+
+    /// The box having `a` and `b` as opposite corners.
+    pub fn from_points(a: Point3, b: Point3) -> Self {
+        Self::new(
+            Interval::new(a.x().min(b.x()), a.x().max(b.x())),
+            Interval::new(a.y().min(b.y()), a.y().max(b.y())),
+            Interval::new(a.z().min(b.z()), a.z().max(b.z())),
+        )
+    }
+
+    /// The smallest box containing both `a` and `b`.
+    pub fn surround(a: &Aabb, b: &Aabb) -> Self {
+        Self {
+            x: Interval::union(a.x, b.x),
+            y: Interval::union(a.y, b.y),
+            z: Interval::union(a.z, b.z),
+        }
+    }
+
+    /// Gives flat boxes a minimal thickness, so that rays can still hit them.
+    fn pad(self) -> Self {
+        let delta: f64 = 1e-4;
+        let pad = |i: Interval| if i.size() < delta { i.expand(delta) } else { i };
+        Self { x: pad(self.x), y: pad(self.y), z: pad(self.z) }
+    }
+
+    pub fn axis(&self, n: usize) -> &Interval {
+        match n {
+            1 => &self.y,
+            2 => &self.z,
+            _ => &self.x,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.min > self.x.max || self.y.min > self.y.max || self.z.min > self.z.max
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        Point3::new(
+            (self.x.min + self.x.max) / 2.,
+            (self.y.min + self.y.max) / 2.,
+            (self.z.min + self.z.max) / 2.,
+        )
+    }
+
+    pub fn surface_area(&self) -> f64 {
+        if self.is_empty() { return 0. }
+        let (x, y, z) = (self.x.size(), self.y.size(), self.z.size());
+        2. * (x * y + y * z + z * x)
+    }
+
+    /// Checks whether `r` goes through the box for a t value within `ray_t` (slab method).
+    pub fn hit(&self, r: &Ray, ray_t: &Interval) -> bool {
+        let dir: Vec3 = r.dir();
+        self.hit_inv(r.orig(), Vec3::new(1. / dir.x(), 1. / dir.y(), 1. / dir.z()), ray_t)
+    }
+
+    /// Same as [`Aabb::hit`], with the inverse of the ray direction computed by the caller,
+    /// for traversals testing one ray against many boxes.
+    pub fn hit_inv(&self, orig: Point3, inv_dir: Vec3, ray_t: &Interval) -> bool {
+        let (mut t_min, mut t_max) = (ray_t.min, ray_t.max);
+
+        for (slab, o, inv_d) in [(&self.x, orig.x(), inv_dir.x()), (&self.y, orig.y(), inv_dir.y()), (&self.z, orig.z(), inv_dir.z())] {
+            let t0: f64 = (slab.min - o) * inv_d;
+            let t1: f64 = (slab.max - o) * inv_d;
+            let (t0, t1) = if inv_d < 0. { (t1, t0) } else { (t0, t1) };
+
+            if t0 > t_min { t_min = t0 }
+            if t1 < t_max { t_max = t1 }
+            if t_max <= t_min { return false }
+        }
+        true
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::utils::Interval;
+
+use super::{Aabb, HitRecord, Object, Objects};
+
+/// Number of buckets the centroids are sorted into when evaluating the surface area heuristic.
+const SAH_BUCKETS: usize = 12;
+
+/// A node of a bounding volume hierarchy. Its children are either nodes or scene objects.
+///
+/// Scenes use the faster [`FlatBvh`](super::FlatBvh), which stores its nodes in a single array.
+/// This tree is kept as the straightforward version to compare against, being an [`Object`] itself.
+pub struct BvhNode {
+    left: Arc<dyn Object>,
+    right: Arc<dyn Object>,
+    bbox: Aabb,
+}
+
+impl BvhNode {
+    pub fn new(objects: Objects) -> Self {
+        Self::build(objects.objects)
+    }
+
+    fn build(mut objects: Vec<Arc<dyn Object>>) -> Self {
+        let (left, right): (Arc<dyn Object>, Arc<dyn Object>) = match objects.len() {
+            0 => {
+                let empty: Arc<dyn Object> = Arc::new(Objects::new());
+                (empty.clone(), empty)
+            }
+            1 => (objects[0].clone(), objects[0].clone()),
+            2 => (objects[0].clone(), objects[1].clone()),
+            _ => {
+                let mid: usize = sah_partition(&mut objects, |obj| obj.bounding_box()).mid;
+                let right = objects.split_off(mid);
+                (Arc::new(Self::build(objects)), Arc::new(Self::build(right)))
+            }
+        };
+
+        let bbox = Aabb::surround(&left.bounding_box(), &right.bounding_box());
+        Self { left, right, bbox }
+    }
+}
+
+impl Object for BvhNode {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        if !self.bbox.hit(r, ray_t) {
+            return false
+        }
+
+        let hit_left: bool = self.left.hit(r, ray_t, rec);
+        let right_t = Interval::new(ray_t.min, if hit_left { rec.t } else { ray_t.max });
+        let hit_right: bool = self.right.hit(r, &right_t, rec);
+
+        hit_left || hit_right
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        let mut materials: Vec<Arc<dyn Material>> = self.left.materials();
+        materials.extend(self.right.materials());
+        materials
+    }
+}
+
+/// Where a set of primitives was split by [`sah_partition`].
+pub(crate) struct SahSplit {
+    /// Index of the first item of the second half.
+    pub mid: usize,
+    /// Axis along which the centroids were sorted.
+    pub axis: usize,
+    /// Estimated cost of the split, in primitive intersections per ray reaching the parent.
+    pub cost: f64,
+}
+
+/// Reorders `items` into two halves according to the surface area heuristic, evaluated over
+/// [`SAH_BUCKETS`] centroid buckets on each axis. Both halves are non-empty when there are at least 2 items.
+pub(crate) fn sah_partition<T>(items: &mut [T], bbox_of: impl Fn(&T) -> Aabb) -> SahSplit {
+    let n: usize = items.len();
+    let boxes: Vec<Aabb> = items.iter().map(&bbox_of).collect();
+    let bounds: Aabb = boxes.iter().fold(Aabb::EMPTY, |acc, b| Aabb::surround(&acc, b));
+    let centroids: Aabb = boxes.iter().fold(Aabb::EMPTY, |acc, b| {
+        let c = b.centroid();
+        Aabb::surround(&acc, &Aabb { x: Interval::new(c.x(), c.x()), y: Interval::new(c.y(), c.y()), z: Interval::new(c.z(), c.z()) })
+    });
+
+    let bucket_of = |b: &Aabb, axis: usize| -> usize {
+        let extent: &Interval = centroids.axis(axis);
+        let c: f64 = b.centroid().axis(axis);
+        (((c - extent.min) / extent.size() * SAH_BUCKETS as f64) as usize).min(SAH_BUCKETS - 1)
+    };
+
+    // (axis, bucket boundary, relative cost)
+    let mut best: Option<(usize, usize, f64)> = None;
+    for axis in 0..3 {
+        if centroids.axis(axis).size() <= 0. {
+            continue
+        }
+
+        let mut counts = [0usize; SAH_BUCKETS];
+        let mut bucket_boxes = [Aabb::EMPTY; SAH_BUCKETS];
+        for b in boxes.iter() {
+            let i = bucket_of(b, axis);
+            counts[i] += 1;
+            bucket_boxes[i] = Aabb::surround(&bucket_boxes[i], b);
+        }
+
+        // Sweep from the right to get the area of every suffix, then from the left to evaluate each boundary.
+        let mut right_area = [0.; SAH_BUCKETS];
+        let mut acc: Aabb = Aabb::EMPTY;
+        for i in (1..SAH_BUCKETS).rev() {
+            acc = Aabb::surround(&acc, &bucket_boxes[i]);
+            right_area[i] = acc.surface_area();
+        }
+
+        let mut acc: Aabb = Aabb::EMPTY;
+        let mut left_count: usize = 0;
+        for boundary in 1..SAH_BUCKETS {
+            acc = Aabb::surround(&acc, &bucket_boxes[boundary - 1]);
+            left_count += counts[boundary - 1];
+            let right_count: usize = n - left_count;
+            if left_count == 0 || right_count == 0 {
+                continue
+            }
+
+            let cost: f64 = (acc.surface_area() * left_count as f64 + right_area[boundary] * right_count as f64) / bounds.surface_area();
+            if best.is_none_or(|(_, _, c)| cost < c) {
+                best = Some((axis, boundary, cost));
+            }
+        }
+    }
+
+    match best {
+        Some((axis, boundary, cost)) => {
+            let mut mid: usize = 0;
+            for i in 0..n {
+                if bucket_of(&bbox_of(&items[i]), axis) < boundary {
+                    items.swap(i, mid);
+                    mid += 1;
+                }
+            }
+            SahSplit { mid, axis, cost }
+        }
+        // All centroids coincide: any split is as good as another.
+        None => SahSplit { mid: n / 2, axis: 0, cost: n as f64 },
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn bvh_matches_linear_search() {
+    use crate::materials::Lambertian;
+    use crate::objects::Sphere;
+    use crate::vec3::{Color3, Point3, Vec3};
+
+    fastrand::seed(7);
+    let mat = Arc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+    let mut objects = Objects::new();
+    let mut reference = Objects::new();
+    for _ in 0..200 {
+        let center = Point3::rand(-10., 10.);
+        let radius = fastrand::f64() * 0.8 + 0.1;
+        objects.add(Sphere::new(center, radius, mat.clone()));
+        reference.add(Sphere::new(center, radius, mat.clone()));
+    }
+    let bvh = BvhNode::new(objects);
+
+    for _ in 0..1000 {
+        let r = Ray::new(Point3::rand(-15., 15.), Vec3::rand_unit());
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let (mut rec, mut rec_ref) = (HitRecord::new(), HitRecord::new());
+
+        let hit = bvh.hit(&r, &ray_t, &mut rec);
+        assert!(hit == reference.hit(&r, &ray_t, &mut rec_ref));
+        assert!(!hit || rec.t == rec_ref.t);
+    }
+}
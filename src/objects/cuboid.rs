@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use fastrand::usize;
+
+use crate::error::{Error, Result};
+use crate::materials::Material;
+use crate::utils::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Vec3, Point3};
+
+use super::{Aabb, HitRecord, Object, Quad, SurfaceSample};
+
+
+/// An axis-aligned box, as six quads facing out.
+pub struct Cuboid {
+    faces: [Quad; 6],
+    bbox: Aabb,
+}
+
+impl Cuboid {
+    /// The box having `a` and `b` as opposite corners.
+    ///
+    /// Fails if the corners coincide along an axis, which leaves the box flat.
+    pub fn new(a: Point3, b: Point3, mat: Arc<dyn Material>) -> Result<Self> {
+        if (0..3).any(|axis| a.axis(axis) == b.axis(axis)) {
+            return Err(Error::invalid_parameter("the corners of a box must differ along every axis"));
+        }
+        let min: Point3 = Point3::new(a.x().min(b.x()), a.y().min(b.y()), a.z().min(b.z()));
+        let max: Point3 = Point3::new(a.x().max(b.x()), a.y().max(b.y()), a.z().max(b.z()));
+        let dx: Vec3 = Vec3::new(max.x() - min.x(), 0., 0.);
+        let dy: Vec3 = Vec3::new(0., max.y() - min.y(), 0.);
+        let dz: Vec3 = Vec3::new(0., 0., max.z() - min.z());
+
+        Ok(Self {
+            faces: [
+                Quad::new(Point3::new(min.x(), min.y(), max.z()), dx, dy, mat.clone())?,  // +Z
+                Quad::new(Point3::new(max.x(), min.y(), max.z()), -dz, dy, mat.clone())?, // +X
+                Quad::new(Point3::new(max.x(), min.y(), min.z()), -dx, dy, mat.clone())?, // -Z
+                Quad::new(Point3::new(min.x(), min.y(), min.z()), dz, dy, mat.clone())?,  // -X
+                Quad::new(Point3::new(min.x(), max.y(), max.z()), dx, -dz, mat.clone())?, // +Y
+                Quad::new(Point3::new(min.x(), min.y(), min.z()), dx, dz, mat)?,          // -Y
+            ],
+            bbox: Aabb::from_points(min, max),
+        })
+    }
+
+    /// The faces seen from `origin`, the only ones worth sampling.
+    fn faces_toward(&self, origin: Point3) -> Vec<&Quad> {
+        self.faces.iter().filter(|face| face.faces(origin)).collect()
+    }
+}
+
+impl Object for Cuboid {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let mut ray_t: Interval = *ray_t;
+        let mut anyhit: bool = false;
+        for face in &self.faces {
+            if face.hit(r, &ray_t, rec) {
+                anyhit = true;
+                ray_t.max = rec.t;
+            }
+        }
+        anyhit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        self.faces[0].materials()
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.faces[0].is_emissive()
+    }
+
+    /// Picks one of the faces seen from `origin` uniformly, then a point on it.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let faces: Vec<&Quad> = self.faces_toward(origin);
+        if faces.is_empty() {
+            return None
+        }
+        let sample: SurfaceSample = faces[usize(..faces.len())].sample_surface(origin)?;
+        Some(SurfaceSample { pdf: sample.pdf / faces.len() as f64, ..sample })
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        let faces: Vec<&Quad> = self.faces_toward(origin);
+        if faces.is_empty() {
+            return 0.
+        }
+        let sum: f64 = faces.iter().map(|face| face.pdf_value(origin, dir)).sum();
+        sum / faces.len() as f64
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn cuboid_hits() {
+    use crate::materials::Lambertian;
+    use crate::vec3::Color3;
+
+    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color3::new(1., 1., 1.)));
+    let cuboid = Cuboid::new(Point3::new(1., 2., 3.), Point3::new(-1., 0., -1.), mat.clone()).unwrap();
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+
+    // Every face is hit from outside on its front, and from inside on its back.
+    let axes: [Vec3; 3] = [Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.), Vec3::new(0., 0., 1.)];
+    let center: Point3 = Point3::new(0., 1., 1.);
+    for axis in axes.into_iter().flat_map(|a| [a, -a]) {
+        let mut rec: HitRecord = HitRecord::new();
+        assert!(cuboid.hit(&Ray::new(center + 10. * axis, -axis), &ray_t, &mut rec));
+        assert!(rec.front && rec.normal == axis);
+        assert!((0. ..=1.).contains(&rec.u) && (0. ..=1.).contains(&rec.v));
+        assert!(cuboid.hit(&Ray::new(center, axis), &ray_t, &mut rec));
+        assert!(!rec.front && rec.normal == -axis);
+    }
+    let mut rec: HitRecord = HitRecord::new();
+    assert!(cuboid.hit(&Ray::new(Point3::new(0.5, 1.5, 10.), Vec3::new(0., 0., -1.)), &ray_t, &mut rec));
+    assert_eq!(rec.t, 7.);
+    assert_eq!(cuboid.bounding_box(), Aabb::from_points(Point3::new(-1., 0., -1.), Point3::new(1., 2., 3.)));
+    assert!(Cuboid::new(Point3::new(0., 0., 0.), Point3::new(1., 0., 1.), mat).is_err());
+
+    // Only the faces toward the origin are sampled, with the density `pdf_value` gives.
+    fastrand::seed(8);
+    let origin: Point3 = Point3::new(3., 5., 0.5);
+    for _ in 0..100 {
+        let sample: SurfaceSample = cuboid.sample_surface(origin).unwrap();
+        assert!((sample.p.x() - 1.).abs() < 1e-12 || (sample.p.y() - 2.).abs() < 1e-12);
+        assert!((cuboid.pdf_value(origin, sample.p - origin) - sample.pdf).abs() < 1e-9 * sample.pdf);
+    }
+    assert!(cuboid.sample_surface(center).is_none());
+}
@@ -0,0 +1,146 @@
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use fastrand::f64;
+
+use crate::error::{Error, Result};
+use crate::materials::Material;
+use crate::utils::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Vec3, Point3};
+
+use super::quad::{area_to_solid_angle, plane_hit};
+use super::{Aabb, HitRecord, Object, SurfaceSample};
+
+
+/// A flat disk, its front face toward `normal`.
+pub struct Disk {
+    center: Point3,
+    radius: f64,
+    normal: Vec3,
+    /// Directions in the plane of the disk, the first one where `u` is 0.
+    tangent: (Vec3, Vec3),
+    mat: Arc<dyn Material>,
+}
+
+impl Disk {
+    /// Fails if `normal` is zero or `radius` isn't positive.
+    pub fn new(center: Point3, normal: Vec3, radius: f64, mat: Arc<dyn Material>) -> Result<Self> {
+        if normal.len_squared() == 0. || !normal.len_squared().is_finite() {
+            return Err(Error::invalid_parameter("the normal of a disk must not be zero"));
+        }
+        if !(radius > 0. && radius.is_finite()) {
+            return Err(Error::invalid_parameter("the radius of a disk must be positive"));
+        }
+        let normal: Vec3 = normal.unit();
+        Ok(Self {
+            center,
+            radius,
+            normal,
+            tangent: normal.orthonormal_basis(),
+            mat,
+        })
+    }
+
+    fn intersect(&self, r: &Ray, ray_t: &Interval) -> Option<(f64, Point3)> {
+        let (t, p) = plane_hit(r, self.center, self.normal, ray_t)?;
+        if (p - self.center).len_squared() > self.radius * self.radius {
+            return None
+        }
+        Some((t, p))
+    }
+
+    fn area(&self) -> f64 {
+        PI * self.radius * self.radius
+    }
+}
+
+impl Object for Disk {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, p)) = self.intersect(r, ray_t) else { return false };
+
+        rec.t = t;
+        rec.p = p;
+        rec.set_normal(r, self.normal);
+        // Polar coordinates: `u` turns around the center, `v` goes out to the rim.
+        let to_p: Vec3 = p - self.center;
+        let phi: f64 = (to_p * self.tangent.1).atan2(to_p * self.tangent.0);
+        let rho: f64 = to_p.len();
+        (rec.u, rec.v) = (if phi < 0. { phi / (2. * PI) + 1. } else { phi / (2. * PI) }, rho / self.radius);
+        // Texture coordinates cover 2 pi rho R of surface per unit of area.
+        rec.set_differentials(r, 1. / (2. * PI * rho.max(1e-4 * self.radius) * self.radius).sqrt());
+        rec.vertex_color = None;
+        rec.mat = self.mat.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        // How far the rim reaches along each axis.
+        let extent = |n: f64| self.radius * (1. - n * n).max(0.).sqrt();
+        let r: Vec3 = Vec3::new(extent(self.normal.x()), extent(self.normal.y()), extent(self.normal.z()));
+        Aabb::from_points(self.center - r, self.center + r)
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![self.mat.clone()]
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    /// Samples the area of the disk uniformly.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let rho: f64 = self.radius * f64().sqrt();
+        let phi: f64 = 2. * PI * f64();
+        let p: Point3 = self.center + rho * phi.cos() * self.tangent.0 + rho * phi.sin() * self.tangent.1;
+
+        let pdf: f64 = area_to_solid_angle(p - origin, self.normal, self.area());
+        if pdf <= 0. { return None }
+        Some(SurfaceSample { p, pdf })
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        match self.intersect(&Ray::new(origin, dir), &Interval::new(0.001, f64::INFINITY)) {
+            Some((t, _)) => area_to_solid_angle(t * dir, self.normal, self.area()),
+            None => 0.,
+        }
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn disk_hits() {
+    use crate::materials::Lambertian;
+    use crate::vec3::Color3;
+
+    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color3::new(1., 1., 1.)));
+    let disk = Disk::new(Point3::new(1., 2., 3.), Vec3::new(0., 0., 2.), 2., mat.clone()).unwrap();
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+
+    let mut rec: HitRecord = HitRecord::new();
+    assert!(disk.hit(&Ray::new(Point3::new(2., 2., 5.), Vec3::new(0., 0., -1.)), &ray_t, &mut rec));
+    assert_eq!(rec.t, 2.);
+    assert_eq!(rec.normal, Vec3::new(0., 0., 1.));
+    assert!(rec.front && (rec.v - 0.5).abs() < 1e-12 && (0. ..1.).contains(&rec.u));
+    assert!(!disk.hit(&Ray::new(Point3::new(2.5, 3.5, 5.), Vec3::new(0., 0., -1.)), &ray_t, &mut rec));
+
+    let bbox: Aabb = disk.bounding_box();
+    assert!(bbox.x.min == -1. && bbox.x.max == 3. && bbox.y.min == 0. && bbox.y.max == 4.);
+    assert!(bbox.z.size() < 1e-3);
+    assert!(Disk::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 0.), 1., mat.clone()).is_err());
+    assert!(Disk::new(Point3::new(0., 0., 0.), Vec3::new(0., 1., 0.), 0., mat).is_err());
+
+    // Sampled points are on the disk, with the density `pdf_value` gives.
+    fastrand::seed(6);
+    let origin: Point3 = Point3::new(0., 1., 6.);
+    for _ in 0..100 {
+        let sample: SurfaceSample = disk.sample_surface(origin).unwrap();
+        assert!((sample.p.z() - 3.).abs() < 1e-12 && (sample.p - Point3::new(1., 2., 3.)).len() <= 2. + 1e-12);
+        assert!((disk.pdf_value(origin, sample.p - origin) - sample.pdf).abs() < 1e-9 * sample.pdf);
+    }
+}
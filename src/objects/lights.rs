@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use fastrand::usize;
+
+use crate::vec3::{Point3, Vec3};
+
+use super::{Object, Objects, SurfaceSample};
+
+/// The emissive objects of a scene, sampled toward for direct lighting.
+pub struct Lights {
+    lights: Vec<Arc<dyn Object>>,
+}
+
+impl Lights {
+    pub fn new(objects: &Objects) -> Self {
+        Self {
+            lights: objects.objects.iter().filter(|obj| obj.is_emissive()).cloned().collect(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.lights.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+
+    /// Picks a light uniformly, then a point on it. The density accounts for both choices.
+    pub fn sample(&self, origin: Point3) -> Option<SurfaceSample> {
+        if self.lights.is_empty() {
+            return None
+        }
+        let sample: SurfaceSample = self.lights[usize(..self.lights.len())].sample_surface(origin)?;
+        Some(SurfaceSample { pdf: sample.pdf / self.lights.len() as f64, ..sample })
+    }
+
+    /// Returns the density with which `sample` picks the direction `dir` from `origin`.
+    pub fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        if self.lights.is_empty() {
+            return 0.
+        }
+        let sum: f64 = self.lights.iter().map(|light| light.pdf_value(origin, dir)).sum();
+        sum / self.lights.len() as f64
+    }
+}
@@ -0,0 +1,278 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::materials::Material;
+use crate::ray::Ray;
+use crate::utils::Interval;
+use crate::vec3::Vec3;
+
+use super::bvh::sah_partition;
+use super::{Aabb, HitRecord, Object, Objects};
+
+/// Leaves never hold more primitives than this.
+const MAX_LEAF_SIZE: usize = 4;
+/// Depth at which nodes are made leaves regardless of their size. Also bounds the traversal stack.
+const MAX_DEPTH: usize = 64;
+/// Cost of visiting a node, relative to intersecting one primitive.
+const TRAVERSAL_COST: f64 = 0.125;
+
+/// A node of a [`LinearBvh`].
+/// Interior nodes are immediately followed by their first child, and point to the second one with `offset`.
+/// Leaves cover the `count` primitives starting at `offset`.
+#[derive(Debug, Clone, Copy)]
+struct LinearNode {
+    bbox: Aabb,
+    offset: u32,
+    count: u32,
+    axis: u8,
+}
+
+/// A bounding volume hierarchy stored as a contiguous list of nodes, traversed without recursion.
+/// It only knows the primitives by index, so that its owner can store them however it likes.
+pub struct LinearBvh {
+    nodes: Vec<LinearNode>,
+}
+
+impl LinearBvh {
+    /// Builds the hierarchy over primitives having the bounding boxes `boxes`, with the surface area heuristic.
+    /// Also returns the order in which the primitives must be stored: leaves refer to ranges of that order.
+    pub fn build(boxes: &[Aabb]) -> (Self, Vec<usize>) {
+        let mut order: Vec<usize> = (0..boxes.len()).collect();
+        let mut bvh = Self { nodes: Vec::with_capacity(2 * boxes.len()) };
+        if !boxes.is_empty() {
+            bvh.build_node(boxes, &mut order, 0, 0);
+        }
+        (bvh, order)
+    }
+
+    /// Pushes the subtree over `items`, which start at `offset` in the final order.
+    fn build_node(&mut self, boxes: &[Aabb], items: &mut [usize], offset: usize, depth: usize) {
+        let bbox: Aabb = items.iter().fold(Aabb::EMPTY, |acc, &i| Aabb::surround(&acc, &boxes[i]));
+        let index: usize = self.nodes.len();
+        self.nodes.push(LinearNode { bbox, offset: offset as u32, count: items.len() as u32, axis: 0 });
+
+        if items.len() <= 1 || depth + 1 >= MAX_DEPTH {
+            return
+        }
+        let split = sah_partition(items, |&i| boxes[i]);
+        if items.len() <= MAX_LEAF_SIZE && TRAVERSAL_COST + split.cost >= items.len() as f64 {
+            return
+        }
+
+        let (left, right) = items.split_at_mut(split.mid);
+        self.build_node(boxes, left, offset, depth + 1);
+        let second: usize = self.nodes.len();
+        self.build_node(boxes, right, offset + split.mid, depth + 1);
+        self.nodes[index] = LinearNode { bbox, offset: second as u32, count: 0, axis: split.axis as u8 };
+    }
+
+    pub fn bounding_box(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |node| node.bbox)
+    }
+
+    /// Walks the nodes pierced by `r` front to back, calling `hit_prim` with the index of each primitive
+    /// (in build order) met within the current `ray_t`. `ray_t` shrinks to the closest hit found so far.
+    pub fn hit_with(
+        &self,
+        r: &Ray,
+        ray_t: &Interval,
+        rec: &mut HitRecord,
+        mut hit_prim: impl FnMut(usize, &Ray, &Interval, &mut HitRecord) -> bool,
+    ) -> bool {
+        if self.nodes.is_empty() {
+            return false
+        }
+
+        let dir: Vec3 = r.dir();
+        let inv_dir: Vec3 = Vec3::new(1. / dir.x(), 1. / dir.y(), 1. / dir.z());
+        let dir_neg: [bool; 3] = [inv_dir.x() < 0., inv_dir.y() < 0., inv_dir.z() < 0.];
+
+        let mut ray_t: Interval = *ray_t;
+        let mut anyhit: bool = false;
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len: usize = 0;
+        let mut current: usize = 0;
+
+        loop {
+            let node: &LinearNode = &self.nodes[current];
+            let mut descend: Option<usize> = None;
+
+            if node.bbox.hit_inv(r.orig(), inv_dir, &ray_t) {
+                if node.count > 0 {
+                    for i in node.offset as usize..(node.offset + node.count) as usize {
+                        if hit_prim(i, r, &ray_t, rec) {
+                            anyhit = true;
+                            ray_t.max = rec.t;
+                        }
+                    }
+                } else if dir_neg[node.axis as usize] {
+                    stack[stack_len] = current + 1;
+                    stack_len += 1;
+                    descend = Some(node.offset as usize);
+                } else {
+                    stack[stack_len] = node.offset as usize;
+                    stack_len += 1;
+                    descend = Some(current + 1);
+                }
+            }
+
+            current = match descend {
+                Some(next) => next,
+                None if stack_len > 0 => {
+                    stack_len -= 1;
+                    stack[stack_len]
+                }
+                None => break,
+            };
+        }
+
+        anyhit
+    }
+
+    /// Gathers figures on the shape of the hierarchy.
+    pub fn stats(&self) -> BvhStats {
+        let mut stats = BvhStats {
+            nodes: self.nodes.len(),
+            leaves: 0,
+            primitives: 0,
+            max_depth: 0,
+            sah_cost: 0.,
+            min_leaf_size: usize::MAX,
+            max_leaf_size: 0,
+        };
+        let root_area: f64 = self.bounding_box().surface_area();
+        if self.nodes.is_empty() || root_area <= 0. {
+            stats.min_leaf_size = 0;
+            return stats
+        }
+
+        let mut stack: Vec<(usize, usize)> = vec![(0, 1)];
+        while let Some((index, depth)) = stack.pop() {
+            let node: &LinearNode = &self.nodes[index];
+            let relative_area: f64 = node.bbox.surface_area() / root_area;
+            stats.max_depth = stats.max_depth.max(depth);
+
+            if node.count > 0 {
+                let size: usize = node.count as usize;
+                stats.leaves += 1;
+                stats.primitives += size;
+                stats.min_leaf_size = stats.min_leaf_size.min(size);
+                stats.max_leaf_size = stats.max_leaf_size.max(size);
+                stats.sah_cost += relative_area * size as f64;
+            } else {
+                stats.sah_cost += relative_area * TRAVERSAL_COST;
+                stack.push((index + 1, depth + 1));
+                stack.push((node.offset as usize, depth + 1));
+            }
+        }
+        stats
+    }
+}
+
+/// Build-time figures of a [`LinearBvh`], to tune it on big scenes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub primitives: usize,
+    pub max_depth: usize,
+    /// Expected cost of a ray going through the root, in primitive intersections.
+    pub sah_cost: f64,
+    pub min_leaf_size: usize,
+    pub max_leaf_size: usize,
+}
+
+impl BvhStats {
+    pub fn avg_leaf_size(&self) -> f64 {
+        if self.leaves == 0 { 0. } else { self.primitives as f64 / self.leaves as f64 }
+    }
+}
+
+impl fmt::Display for BvhStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "BVH: {} nodes, {} leaves, {} primitives", self.nodes, self.leaves, self.primitives)?;
+        writeln!(f, "  max depth: {}", self.max_depth)?;
+        writeln!(f, "  SAH cost:  {:.3}", self.sah_cost)?;
+        write!(f, "  leaf size: {} min, {} max, {:.2} avg", self.min_leaf_size, self.max_leaf_size, self.avg_leaf_size())
+    }
+}
+
+
+/// Scene objects sorted into a [`LinearBvh`].
+pub struct FlatBvh {
+    bvh: LinearBvh,
+    objects: Vec<Arc<dyn Object>>,
+    /// Index of each object in the `Objects` it was built from.
+    ids: Vec<u32>,
+}
+
+impl FlatBvh {
+    pub fn new(objects: Objects) -> Self {
+        let boxes: Vec<Aabb> = objects.objects.iter().map(|obj| obj.bounding_box()).collect();
+        let (bvh, order) = LinearBvh::build(&boxes);
+        let ids: Vec<u32> = order.iter().map(|&i| i as u32).collect();
+        let objects: Vec<Arc<dyn Object>> = order.into_iter().map(|i| objects.objects[i].clone()).collect();
+        Self { bvh, objects, ids }
+    }
+
+    pub fn stats(&self) -> BvhStats {
+        self.bvh.stats()
+    }
+}
+
+impl Object for FlatBvh {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        self.bvh.hit_with(r, ray_t, rec, |i, r, ray_t, rec| {
+            let hit: bool = self.objects[i].hit(r, ray_t, rec);
+            if hit {
+                rec.object_id = self.ids[i];
+            }
+            hit
+        })
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        self.objects.iter().flat_map(|obj| obj.materials()).collect()
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn flat_bvh_matches_linear_search() {
+    use crate::materials::Lambertian;
+    use crate::objects::Sphere;
+    use crate::vec3::{Color3, Point3};
+
+    fastrand::seed(11);
+    let mat = Arc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+    let mut objects = Objects::new();
+    let mut reference = Objects::new();
+    for _ in 0..500 {
+        let center = Point3::rand(-10., 10.);
+        let radius = fastrand::f64() * 0.8 + 0.1;
+        objects.add(Sphere::new(center, radius, mat.clone()));
+        reference.add(Sphere::new(center, radius, mat.clone()));
+    }
+    let bvh = FlatBvh::new(objects);
+
+    let stats = bvh.stats();
+    assert!(stats.primitives == 500 && stats.nodes == 2 * stats.leaves - 1);
+    assert!(stats.max_leaf_size <= MAX_LEAF_SIZE && stats.max_depth < MAX_DEPTH);
+
+    for _ in 0..1000 {
+        let r = Ray::new(Point3::rand(-15., 15.), Vec3::rand_unit());
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let (mut rec, mut rec_ref) = (HitRecord::new(), HitRecord::new());
+
+        let hit = bvh.hit(&r, &ray_t, &mut rec);
+        assert!(hit == reference.hit(&r, &ray_t, &mut rec_ref));
+        assert!(!hit || (rec.t == rec_ref.t && rec.object_id == rec_ref.object_id));
+    }
+}
@@ -0,0 +1,334 @@
+use std::sync::Arc;
+
+use fastrand::f64;
+
+use crate::error::{Error, Result};
+use crate::materials::Material;
+use crate::utils::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Color3, Vec3, Point3};
+
+use super::quad::area_to_solid_angle;
+use super::triangle::{barycentric, interpolate_normal, interpolate_uv, intersect, sample_point, uv_scale};
+use super::{Aabb, HitRecord, LinearBvh, Object, SurfaceSample};
+
+
+/// Triangles sharing vertex buffers, indexed by triplets. The triangles are sorted into their own BVH.
+pub struct TriangleMesh {
+    positions: Vec<Point3>,
+    normals: Vec<Vec3>,
+    uvs: Vec<(f64, f64)>,
+    tangents: Vec<Vec3>,
+    colors: Vec<Color3>,
+    indices: Vec<[u32; 3]>,
+    /// Running sum of the areas of the triangles, to sample them by area.
+    areas: Vec<f64>,
+    bvh: LinearBvh,
+    mat: Arc<dyn Material>,
+}
+
+impl TriangleMesh {
+    /// Builds the mesh and its BVH. `indices` refer to `positions`, counter-clockwise when looking at the front face.
+    ///
+    /// Fails if an index is out of bounds.
+    pub fn new(positions: Vec<Point3>, indices: Vec<[u32; 3]>, mat: Arc<dyn Material>) -> Result<Self> {
+        if let Some(&i) = indices.iter().flatten().find(|&&i| i as usize >= positions.len()) {
+            return Err(Error::invalid_parameter(format!("triangle mesh index {} out of bounds, for {} vertices", i, positions.len())));
+        }
+
+        let boxes: Vec<Aabb> = indices.iter().map(|tri| {
+            let [p0, p1, p2] = tri.map(|i| positions[i as usize]);
+            Aabb::surround(&Aabb::from_points(p0, p1), &Aabb::from_points(p2, p2))
+        }).collect();
+        let (bvh, order) = LinearBvh::build(&boxes);
+        let indices: Vec<[u32; 3]> = order.into_iter().map(|i| indices[i]).collect();
+        let areas: Vec<f64> = indices.iter().scan(0., |sum, tri| {
+            let [p0, p1, p2] = tri.map(|i| positions[i as usize]);
+            *sum += ((p1 - p0) & (p2 - p0)).len() / 2.;
+            Some(*sum)
+        }).collect();
+
+        Ok(Self {
+            positions,
+            normals: vec![],
+            uvs: vec![],
+            tangents: vec![],
+            colors: vec![],
+            indices,
+            areas,
+            bvh,
+            mat,
+        })
+    }
+
+    /// Sets per-vertex normals, interpolated over the faces for shading.
+    ///
+    /// Fails if there isn't one normal per vertex.
+    pub fn with_normals(mut self, normals: Vec<Vec3>) -> Result<Self> {
+        self.check_per_vertex(normals.len(), "normal")?;
+        self.normals = normals;
+        Ok(self)
+    }
+
+    /// Sets per-vertex texture coordinates.
+    ///
+    /// Fails if there aren't one pair of coordinates per vertex.
+    pub fn with_uvs(mut self, uvs: Vec<(f64, f64)>) -> Result<Self> {
+        self.check_per_vertex(uvs.len(), "uv")?;
+        self.uvs = uvs;
+        Ok(self)
+    }
+
+    /// Sets per-vertex tangents.
+    ///
+    /// Fails if there isn't one tangent per vertex.
+    pub fn with_tangents(mut self, tangents: Vec<Vec3>) -> Result<Self> {
+        self.check_per_vertex(tangents.len(), "tangent")?;
+        self.tangents = tangents;
+        Ok(self)
+    }
+
+    /// Sets per-vertex colors, which tint the albedo of diffuse materials.
+    ///
+    /// Fails if there isn't one color per vertex.
+    pub fn with_colors(mut self, colors: Vec<Color3>) -> Result<Self> {
+        self.check_per_vertex(colors.len(), "color")?;
+        self.colors = colors;
+        Ok(self)
+    }
+
+    fn check_per_vertex(&self, count: usize, name: &str) -> Result<()> {
+        if count != self.positions.len() {
+            return Err(Error::invalid_parameter(format!("expected one {} per vertex, found {} for {} vertices", name, count, self.positions.len())));
+        }
+        Ok(())
+    }
+
+    /// Replaces the normals by the average of the normals of the faces around each vertex,
+    /// weighted by their area.
+    pub fn with_smooth_normals(mut self) -> Self {
+        let mut normals: Vec<Vec3> = vec![Vec3::default(); self.positions.len()];
+        for tri in self.indices.iter() {
+            let [p0, p1, p2] = tri.map(|i| self.positions[i as usize]);
+            let n: Vec3 = (p1 - p0) & (p2 - p0);
+            for &i in tri {
+                normals[i as usize] += n;
+            }
+        }
+        self.normals = normals.into_iter().map(|n| if n.near_zero() { n } else { n.unit() }).collect();
+        self
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn positions(&self) -> &[Point3] {
+        &self.positions
+    }
+    pub fn normals(&self) -> &[Vec3] {
+        &self.normals
+    }
+    pub fn uvs(&self) -> &[(f64, f64)] {
+        &self.uvs
+    }
+    pub fn tangents(&self) -> &[Vec3] {
+        &self.tangents
+    }
+    pub fn colors(&self) -> &[Color3] {
+        &self.colors
+    }
+    /// Triangles, in BVH order.
+    pub fn indices(&self) -> &[[u32; 3]] {
+        &self.indices
+    }
+
+    /// The corners of the triangle at `index`, in BVH order.
+    fn corners(&self, index: usize) -> [Point3; 3] {
+        self.indices[index].map(|i| self.positions[i as usize])
+    }
+
+    fn total_area(&self) -> f64 {
+        self.areas.last().copied().unwrap_or(0.)
+    }
+
+    fn hit_triangle(&self, index: usize, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let tri: [usize; 3] = self.indices[index].map(|i| i as usize);
+        let p: [Point3; 3] = tri.map(|i| self.positions[i]);
+        let Some((t, bary)) = intersect(r, p, ray_t) else { return false };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.bary = bary;
+        let uvs: [(f64, f64); 3] = if self.uvs.is_empty() {
+            [(0., 0.), (1., 0.), (0., 1.)]
+        } else {
+            tri.map(|i| self.uvs[i])
+        };
+        (rec.u, rec.v) = interpolate_uv(uvs, bary);
+        rec.vertex_color = if self.colors.is_empty() {
+            None
+        } else {
+            let [c0, c1, c2] = tri.map(|i| self.colors[i]);
+            Some(bary.x() * c0 + bary.y() * c1 + bary.z() * c2)
+        };
+
+        let n_outward: Vec3 = ((p[1] - p[0]) & (p[2] - p[0])).unit();
+        if self.normals.is_empty() {
+            rec.set_normal(r, n_outward);
+        } else {
+            let n: [Vec3; 3] = tri.map(|i| self.normals[i]);
+            rec.set_shading_normal(r, n_outward, interpolate_normal(n, bary, n_outward));
+        }
+        rec.set_differentials(r, uv_scale(p, uvs));
+        if !self.normals.is_empty() {
+            let n: [Vec3; 3] = tri.map(|i| self.normals[i]);
+            rec.set_normal_differentials(|q| interpolate_normal(n, barycentric(p, q), n_outward));
+        }
+
+        true
+    }
+}
+
+impl Object for TriangleMesh {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let hit: bool = self.bvh.hit_with(r, ray_t, rec, |i, r, ray_t, rec| self.hit_triangle(i, r, ray_t, rec));
+        if hit {
+            rec.mat = self.mat.clone();
+        }
+        hit
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        self.bvh.bounding_box()
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![self.mat.clone()]
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    /// Picks a triangle with a probability proportional to its area, then a point on it uniformly.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let total: f64 = self.total_area();
+        if total <= 0. { return None }
+        let target: f64 = f64() * total;
+        let index: usize = self.areas.partition_point(|&sum| sum <= target).min(self.areas.len() - 1);
+        let p: [Point3; 3] = self.corners(index);
+        let point: Point3 = sample_point(p);
+
+        let pdf: f64 = area_to_solid_angle(point - origin, ((p[1] - p[0]) & (p[2] - p[0])).unit(), total);
+        if pdf <= 0. || !pdf.is_finite() { return None }
+        Some(SurfaceSample { p: point, pdf })
+    }
+
+    /// Sums the densities of all the triangles crossed along `dir`, since any of them could have been picked.
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        let total: f64 = self.total_area();
+        if total <= 0. { return 0. }
+        let mut pdf: f64 = 0.;
+        let r = Ray::new(origin, dir);
+        self.bvh.hit_with(&r, &Interval::new(0.001, f64::INFINITY), &mut HitRecord::new(), |i, r, ray_t, _| {
+            let p: [Point3; 3] = self.corners(i);
+            if let Some((t, _)) = intersect(r, p, ray_t) {
+                pdf += area_to_solid_angle(t * r.dir(), ((p[1] - p[0]) & (p[2] - p[0])).unit(), total);
+            }
+            // Never a hit, so that the walk goes on to the farther triangles.
+            false
+        });
+        pdf
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn mesh_matches_triangles() {
+    use crate::materials::Lambertian;
+    use crate::objects::{Objects, Triangle};
+
+    // A bumpy 20x20 grid.
+    fastrand::seed(5);
+    let mat = Arc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+    let size: u32 = 21;
+    let positions: Vec<Point3> = (0..size * size)
+        .map(|i| Point3::new((i % size) as f64, fastrand::f64(), (i / size) as f64))
+        .collect();
+    let mut indices: Vec<[u32; 3]> = vec![];
+    for z in 0..size - 1 {
+        for x in 0..size - 1 {
+            let i: u32 = z * size + x;
+            indices.push([i, i + size, i + 1]);
+            indices.push([i + 1, i + size, i + size + 1]);
+        }
+    }
+
+    let mut reference = Objects::new();
+    for tri in indices.iter() {
+        reference.add(Triangle::new(positions[tri[0] as usize], positions[tri[1] as usize], positions[tri[2] as usize], mat.clone()));
+    }
+    assert!(TriangleMesh::new(positions.clone(), vec![[0, 1, size * size]], mat.clone()).is_err());
+    let mesh = TriangleMesh::new(positions, indices, mat).unwrap();
+    assert!(mesh.triangle_count() == 800);
+
+    for _ in 0..1000 {
+        let r = Ray::new(Point3::new(10., 5., 10.) + Point3::rand(-8., 8.), Vec3::rand_unit());
+        let ray_t = Interval::new(0.001, f64::INFINITY);
+        let (mut rec, mut rec_ref) = (HitRecord::new(), HitRecord::new());
+
+        let hit = mesh.hit(&r, &ray_t, &mut rec);
+        assert!(hit == reference.hit(&r, &ray_t, &mut rec_ref));
+        assert!(!hit || (rec.t == rec_ref.t && rec.normal == rec_ref.normal));
+    }
+    assert!(mesh.with_uvs(vec![(0., 0.); 3]).is_err());
+}
+#[test]
+fn vertex_buffers_need_one_item_per_vertex() {
+    use crate::materials::Lambertian;
+
+    let mat = Arc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)));
+    let mesh = || TriangleMesh::new(vec![Point3::new(0., 0., 0.), Point3::new(1., 0., 0.), Point3::new(0., 0., 1.)], vec![[0, 1, 2]], mat.clone()).unwrap();
+    let tangent: Vec3 = Vec3::new(1., 0., 0.);
+
+    let error = mesh().with_tangents(vec![tangent; 2]).err().unwrap().to_string();
+    assert_eq!(error, "invalid parameter: expected one tangent per vertex, found 2 for 3 vertices");
+    assert_eq!(mesh().with_tangents(vec![tangent; 3]).unwrap().tangents(), [tangent; 3]);
+    assert!(mesh().with_normals(vec![]).is_err() && mesh().with_colors(vec![Color3::default(); 4]).is_err());
+}
+#[test]
+fn emissive_mesh_is_a_light() {
+    use crate::materials::{DiffuseLight, Lambertian};
+    use crate::objects::Objects;
+    use crate::scene::Scene;
+
+    // A 2x1 rectangle facing down, split in two triangles of the same area, and a larger one beside.
+    let positions: Vec<Point3> = vec![
+        Point3::new(0., 2., 0.), Point3::new(2., 2., 0.), Point3::new(2., 2., 1.), Point3::new(0., 2., 1.),
+        Point3::new(3., 2., 0.), Point3::new(7., 2., 0.), Point3::new(3., 2., 2.),
+    ];
+    let indices: Vec<[u32; 3]> = vec![[0, 1, 2], [0, 2, 3], [4, 5, 6]];
+    let light = TriangleMesh::new(positions, indices, Arc::new(DiffuseLight::new(Color3::new(4., 4., 4.)))).unwrap();
+    assert!(light.is_emissive());
+
+    fastrand::seed(2);
+    let origin: Point3 = Point3::new(1., 0., 0.5);
+    let mut on_large: u32 = 0;
+    for _ in 0..2000 {
+        let sample: SurfaceSample = light.sample_surface(origin).unwrap();
+        assert!((sample.p.y() - 2.).abs() < 1e-12);
+        assert!((light.pdf_value(origin, sample.p - origin) - sample.pdf).abs() < 1e-9 * sample.pdf);
+        if sample.p.x() > 2.5 { on_large += 1 }
+    }
+    // The large triangle holds 4 of the 6 units of area.
+    assert!((on_large as f64 / 2000. - 4. / 6.).abs() < 0.05);
+
+    let mut objects = Objects::new();
+    objects.add(light);
+    objects.add(TriangleMesh::new(vec![Point3::new(0., 0., 0.), Point3::new(1., 0., 0.), Point3::new(0., 0., 1.)], vec![[0, 1, 2]], Arc::new(Lambertian::new(Color3::new(0.5, 0.5, 0.5)))).unwrap());
+    assert_eq!(Scene::new(objects).lights.len(), 1);
+}
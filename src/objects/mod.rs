@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::materials::Lambertian;
 use crate::materials::Material;
@@ -6,15 +6,51 @@ use crate::utils::Interval;
 use crate::ray::*;
 use crate::vec3::*;
 
+mod aabb;
+pub use aabb::Aabb;
+mod bvh;
+pub use bvh::BvhNode;
+mod cuboid;
+pub use cuboid::Cuboid;
+mod disk;
+pub use disk::Disk;
+mod lights;
+pub use lights::Lights;
+mod linear_bvh;
+pub use linear_bvh::{BvhStats, FlatBvh, LinearBvh};
+mod mesh;
+pub use mesh::TriangleMesh;
+mod quad;
+pub use quad::Quad;
 mod sphere;
 pub use sphere::Sphere;
+mod triangle;
+pub use triangle::Triangle;
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub p: Point3,
     pub normal: Vec3,
     pub t: f64,
+    /// Surface coordinates of the hit point, for texturing.
+    pub u: f64,
+    pub v: f64,
+    /// Width of the footprint of the ray in texture coordinates, to filter textures over. 0 when unknown.
+    pub footprint: f64,
+    /// Offsets of the hit point and of the normal for the next pixels in x and y, from the differentials
+    /// of the ray. Zero for rays without any.
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
+    pub dndx: Vec3,
+    pub dndy: Vec3,
+    /// Barycentric coordinates of the hit point, for triangles.
+    pub bary: Vec3,
+    /// Color interpolated from the vertices of the hit, for meshes having some.
+    pub vertex_color: Option<Color3>,
+    /// Index of the top-level object hit, in the order it was added to the scene.
+    pub object_id: u32,
     pub front: bool,
-    pub mat: Rc<dyn Material>,
+    pub mat: Arc<dyn Material>,
 }
 
 impl HitRecord {
@@ -23,8 +59,18 @@ impl HitRecord {
             p: Point3::new(0., 0., 0.),
             normal: Vec3::new(0., 0., 0.),
             t: 0.,
+            u: 0.,
+            v: 0.,
+            footprint: 0.,
+            dpdx: Vec3::new(0., 0., 0.),
+            dpdy: Vec3::new(0., 0., 0.),
+            dndx: Vec3::new(0., 0., 0.),
+            dndy: Vec3::new(0., 0., 0.),
+            bary: Vec3::new(0., 0., 0.),
+            vertex_color: None,
+            object_id: 0,
             front: false,
-            mat: Rc::new(Lambertian::new(Color3::new(0.1, 0.5, 0.9)))
+            mat: Arc::new(Lambertian::new(Color3::new(0.1, 0.5, 0.9)))
         }
     }
 
@@ -32,43 +78,136 @@ impl HitRecord {
         self.front = r.dir() * n_outward < 0.;
         self.normal = if self.front { n_outward } else { -n_outward }
     }
+
+    /// Same as [`HitRecord::set_normal`], except that the stored normal is `n_shading`,
+    /// flipped to the side of the geometric normal facing the ray.
+    pub fn set_shading_normal(&mut self, r: &Ray, n_outward: Vec3, n_shading: Vec3) {
+        self.front = r.dir() * n_outward < 0.;
+        let n_shading: Vec3 = if n_shading * n_outward < 0. { -n_shading } else { n_shading };
+        self.normal = if self.front { n_shading } else { -n_shading }
+    }
+
+    /// Sets `dpdx`, `dpdy` and `footprint` where the differentials of `r` meet the tangent plane,
+    /// `uv_scale` being the change of texture coordinates per unit of length along the surface.
+    /// Clears the normal offsets, see [`HitRecord::set_normal_differentials`]. Expects the normal to be set.
+    pub fn set_differentials(&mut self, r: &Ray, uv_scale: f64) {
+        let zero: Vec3 = Vec3::new(0., 0., 0.);
+        (self.dndx, self.dndy) = (zero, zero);
+        let offset = |origin: Point3, dir: Vec3| -> Option<Vec3> {
+            let cosine: f64 = self.normal * dir;
+            if cosine.abs() < 1e-12 { return None }
+            let t: f64 = (self.normal * (self.p - origin)) / cosine;
+            Some(origin + t * dir - self.p)
+        };
+        let offsets: Option<(Vec3, Vec3)> = r.differentials().and_then(|d| Some((offset(d.rx_origin, d.rx_dir)?, offset(d.ry_origin, d.ry_dir)?)));
+        (self.dpdx, self.dpdy) = offsets.unwrap_or((zero, zero));
+        // Side of the square having the area of the footprint.
+        self.footprint = (self.dpdx & self.dpdy).len().sqrt() * uv_scale;
+    }
+
+    /// Sets `dndx` and `dndy` for curved surfaces, `normal_at` giving the outward normal at a point near the hit.
+    /// Expects [`HitRecord::set_differentials`] to be called first.
+    pub fn set_normal_differentials(&mut self, normal_at: impl Fn(Point3) -> Vec3) {
+        if self.dpdx.near_zero() && self.dpdy.near_zero() {
+            return;
+        }
+        let facing = |n: Vec3| if n * self.normal < 0. { -n } else { n };
+        self.dndx = facing(normal_at(self.p + self.dpdx)) - self.normal;
+        self.dndy = facing(normal_at(self.p + self.dpdy)) - self.normal;
+    }
 }
 
-pub trait Object {
+impl Default for HitRecord {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub trait Object: Send + Sync {
     /// Checks whether `self` is on the path of `r`, for a t value between `tmin` and `tmax`.
     /// If so, updates `rec` accordingly.
     fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool;
+    /// Returns a box containing the whole object.
+    fn bounding_box(&self) -> Aabb;
     // Each object alaso needs an initializer, fn new()
+
+    /// Whether the object emits light, and should be sampled as a light.
+    fn is_emissive(&self) -> bool {
+        false
+    }
+    /// Picks a random point of the surface seen from `origin`, for objects that can be sampled as lights.
+    fn sample_surface(&self, _origin: Point3) -> Option<SurfaceSample> {
+        None
+    }
+    /// Returns the density, in solid angle, with which `sample_surface` picks the point seen from `origin` along `dir`.
+    fn pdf_value(&self, _origin: Point3, _dir: Vec3) -> f64 {
+        0.
+    }
+    /// Returns the materials the object is made of, so that they can be told apart in the output.
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![]
+    }
 }
 
+/// A point picked on a surface, for light sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceSample {
+    pub p: Point3,
+    /// Density in solid angle, as seen from the origin of the sampling.
+    pub pdf: f64,
+}
 
-pub struct Objects<'a>(Vec<&'a (dyn Object + 'a)>);
 
-impl<'obj> Objects<'obj> {
+pub struct Objects {
+    objects: Vec<Arc<dyn Object>>,
+    bbox: Aabb,
+}
+
+impl Objects {
     pub fn new() -> Self {
-        Objects(vec![])
+        Objects {
+            objects: vec![],
+            bbox: Aabb::EMPTY,
+        }
     }
 
-    pub fn add(&mut self, object: &'obj impl Object) {
-        self.0.push(object)
+    pub fn add(&mut self, object: impl Object + 'static) {
+        self.bbox = Aabb::surround(&self.bbox, &object.bounding_box());
+        self.objects.push(Arc::new(object))
     }
 
     // pub fn clear(&mut self) {
-    //     self.0 = vec![]
+    //     self.objects = vec![]
     // }
+}
 
-    pub fn hit(&self, r: &Ray, mut ray_t: Interval, rec: &mut HitRecord) -> bool {
-        let mut temp_rec: HitRecord = HitRecord::new();
+impl Default for Objects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Object for Objects {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let mut ray_t: Interval = *ray_t;
         let mut anyhit: bool = false;
 
-        for obj in self.0.iter() {
-            if obj.hit(r, &ray_t, &mut temp_rec) {
+        for (i, obj) in self.objects.iter().enumerate() {
+            if obj.hit(r, &ray_t, rec) {
                 anyhit = true;
-                ray_t.max = temp_rec.t;
+                ray_t.max = rec.t;
+                rec.object_id = i as u32;
             }
         }
-        
-        if anyhit { *rec = temp_rec; }
+
         anyhit
     }
-}
\ No newline at end of file
+
+    fn bounding_box(&self) -> Aabb {
+        self.bbox
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        self.objects.iter().flat_map(|obj| obj.materials()).collect()
+    }
+}
@@ -0,0 +1,168 @@
+use std::sync::Arc;
+
+use fastrand::f64;
+
+use crate::error::{Error, Result};
+use crate::materials::Material;
+use crate::utils::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Vec3, Point3};
+
+use super::{Aabb, HitRecord, Object, SurfaceSample};
+
+
+/// A parallelogram, from the corner `q` along the edges `u` and `v`.
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    /// Unit normal, `u` × `v` normalized.
+    normal: Vec3,
+    /// `u` × `v` over its squared length, to find the coordinates of a point along the edges.
+    w: Vec3,
+    area: f64,
+    mat: Arc<dyn Material>,
+}
+
+impl Quad {
+    /// The front face is the one `u` turns counter-clockwise to `v` on.
+    ///
+    /// Fails if the edges are zero or parallel, which leaves no area.
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Result<Self> {
+        let n: Vec3 = u & v;
+        if n.len_squared() == 0. || !n.len_squared().is_finite() {
+            return Err(Error::invalid_parameter("the edges of a quad must not be zero or parallel"));
+        }
+        Ok(Self {
+            q,
+            u,
+            v,
+            normal: n.unit(),
+            w: n / n.len_squared(),
+            area: n.len(),
+            mat,
+        })
+    }
+
+    /// Returns the t value of the hit and its coordinates along the edges, between 0 and 1.
+    fn intersect(&self, r: &Ray, ray_t: &Interval) -> Option<(f64, f64, f64)> {
+        let (t, p) = plane_hit(r, self.q, self.normal, ray_t)?;
+        let to_p: Vec3 = p - self.q;
+        let alpha: f64 = self.w * (to_p & self.v);
+        let beta: f64 = self.w * (self.u & to_p);
+        if !(0. ..=1.).contains(&alpha) || !(0. ..=1.).contains(&beta) {
+            return None
+        }
+        Some((t, alpha, beta))
+    }
+
+    /// Whether `origin` sees the front face.
+    pub(crate) fn faces(&self, origin: Point3) -> bool {
+        self.normal * (origin - self.q) > 0.
+    }
+}
+
+impl Object for Quad {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, alpha, beta)) = self.intersect(r, ray_t) else { return false };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        (rec.u, rec.v) = (alpha, beta);
+        rec.set_normal(r, self.normal);
+        // The texture coordinates stretch over the whole quad.
+        rec.set_differentials(r, 1. / self.area.sqrt());
+        rec.vertex_color = None;
+        rec.mat = self.mat.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::surround(
+            &Aabb::from_points(self.q, self.q + self.u + self.v),
+            &Aabb::from_points(self.q + self.u, self.q + self.v),
+        )
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![self.mat.clone()]
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    /// Samples the area of the quad uniformly.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let p: Point3 = self.q + f64() * self.u + f64() * self.v;
+        let pdf: f64 = area_to_solid_angle(p - origin, self.normal, self.area);
+        if pdf <= 0. { return None }
+        Some(SurfaceSample { p, pdf })
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        match self.intersect(&Ray::new(origin, dir), &Interval::new(0.001, f64::INFINITY)) {
+            Some((t, _, _)) => area_to_solid_angle(t * dir, self.normal, self.area),
+            None => 0.,
+        }
+    }
+}
+
+/// Where `r` crosses the plane through `q` facing `normal`, within `ray_t`.
+pub(crate) fn plane_hit(r: &Ray, q: Point3, normal: Vec3, ray_t: &Interval) -> Option<(f64, Point3)> {
+    let denom: f64 = normal * r.dir();
+    if denom.abs() < 1e-12 {
+        return None
+    }
+    let t: f64 = normal * (q - r.orig()) / denom;
+    if !ray_t.surrounds(t) {
+        return None
+    }
+    Some((t, r.at(t)))
+}
+
+/// Converts the uniform density over a flat surface of `area` to a solid angle density, for the point at `to_p` from the origin.
+pub(crate) fn area_to_solid_angle(to_p: Vec3, normal: Vec3, area: f64) -> f64 {
+    let cosine: f64 = (to_p.unit() * normal).abs();
+    if area <= 0. || cosine <= 0. { return 0. }
+    to_p.len_squared() / (cosine * area)
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn quad_hits() {
+    use crate::materials::Lambertian;
+    use crate::vec3::Color3;
+
+    let mat: Arc<dyn Material> = Arc::new(Lambertian::new(Color3::new(1., 1., 1.)));
+    let quad = Quad::new(Point3::new(-1., 0., -1.), Vec3::new(2., 0., 0.), Vec3::new(0., 0., 4.), mat.clone()).unwrap();
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+
+    // Seen from below, which `u` × `v` points to.
+    let mut rec: HitRecord = HitRecord::new();
+    assert!(quad.hit(&Ray::new(Point3::new(0.5, -2., 2.), Vec3::new(0., 1., 0.)), &ray_t, &mut rec));
+    assert_eq!(rec.t, 2.);
+    assert!((rec.u - 0.75).abs() < 1e-12 && (rec.v - 0.75).abs() < 1e-12);
+    assert_eq!(rec.normal, Vec3::new(0., -1., 0.));
+    assert!(rec.front);
+
+    assert!(!quad.hit(&Ray::new(Point3::new(1.5, 2., 0.), Vec3::new(0., -1., 0.)), &ray_t, &mut rec));
+    assert!(!quad.hit(&Ray::new(Point3::new(0., 2., 0.), Vec3::new(1., 0., 0.)), &ray_t, &mut rec));
+    let bbox: Aabb = quad.bounding_box();
+    assert!(bbox.x.min == -1. && bbox.x.max == 1. && bbox.z.min == -1. && bbox.z.max == 3.);
+    assert!(Quad::new(Point3::new(0., 0., 0.), Vec3::new(1., 0., 0.), Vec3::new(-2., 0., 0.), mat.clone()).is_err());
+    assert!(Quad::new(Point3::new(0., 0., 0.), Vec3::new(0., 0., 0.), Vec3::new(0., 0., 1.), mat).is_err());
+
+    // Sampled points are on the quad, with the density `pdf_value` gives.
+    fastrand::seed(4);
+    let origin: Point3 = Point3::new(0.3, 3., 0.2);
+    for _ in 0..100 {
+        let sample: SurfaceSample = quad.sample_surface(origin).unwrap();
+        assert!(sample.p.y().abs() < 1e-12);
+        assert!((quad.pdf_value(origin, sample.p - origin) - sample.pdf).abs() < 1e-9 * sample.pdf);
+    }
+}
@@ -1,27 +1,46 @@
-use std::rc::Rc;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+use fastrand::f64;
 
 use crate::materials::Material;
 use crate::utils::Interval;
 use crate::ray::Ray;
 use crate::vec3::{Vec3, Point3};
 
-use super::{HitRecord, Object};
+use super::{Aabb, HitRecord, Object, SurfaceSample};
 
 
 pub struct Sphere {
     center: Point3,
     radius: f64,
-    mat: Rc<dyn Material>
+    mat: Arc<dyn Material>
 }
 
 impl Sphere {
-    pub fn new(center: Point3, radius: f64, mat: Rc<dyn Material>) -> Self {
+    pub fn new(center: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
         Self {
             center,
             radius,
             mat,
         }
     }
+
+    /// Cosine of the half-angle of the cone under which the sphere is seen from `origin`, if outside of it.
+    fn cos_theta_max(&self, origin: Point3) -> Option<f64> {
+        let dist_squared: f64 = (self.center - origin).len_squared();
+        if dist_squared <= self.radius * self.radius {
+            return None
+        }
+        Some((1. - self.radius * self.radius / dist_squared).sqrt())
+    }
+}
+
+/// Spherical coordinates of a point `p` of the unit sphere: `u` turns around the Y axis from -X, `v` goes from the bottom to the top.
+fn sphere_uv(p: Point3) -> (f64, f64) {
+    let theta: f64 = (-p.y()).clamp(-1., 1.).acos();
+    let phi: f64 = (-p.z()).atan2(p.x()) + PI;
+    (phi / (2. * PI), theta / PI)
 }
 
 impl Object for Sphere {
@@ -48,8 +67,60 @@ impl Object for Sphere {
         rec.p = r.at(rec.t);
         let n_outward: Vec3 = (rec.p - self.center) / self.radius;
         rec.set_normal(r, n_outward);
+        (rec.u, rec.v) = sphere_uv(n_outward);
+        // Texture coordinates cover 2 pi^2 r^2 sin(theta) of surface per unit of area.
+        let sin_theta: f64 = (1. - n_outward.y() * n_outward.y()).max(1e-4).sqrt();
+        rec.set_differentials(r, 1. / (PI * self.radius * (2. * sin_theta).sqrt()));
+        rec.set_normal_differentials(|q| (q - self.center).unit());
+        rec.vertex_color = None;
         rec.mat = self.mat.clone();
 
         true
     }
+
+    fn bounding_box(&self) -> Aabb {
+        let r: Vec3 = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::from_points(self.center - r, self.center + r)
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![self.mat.clone()]
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    /// Samples the cone of directions under which the sphere is seen, uniformly.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let cos_theta_max: f64 = self.cos_theta_max(origin)?;
+        let z: f64 = 1. + f64() * (cos_theta_max - 1.);
+        let phi: f64 = 2. * PI * f64();
+        let sin_theta: f64 = (1. - z * z).max(0.).sqrt();
+
+        let w: Vec3 = (self.center - origin).unit();
+        let (u, v) = w.orthonormal_basis();
+        let dir: Vec3 = phi.cos() * sin_theta * u + phi.sin() * sin_theta * v + z * w;
+
+        // Distance to the near side of the sphere along `dir`.
+        let oc: Vec3 = origin - self.center;
+        let half_b: f64 = oc * dir;
+        let c: f64 = oc.len_squared() - self.radius * self.radius;
+        let t: f64 = -half_b - (half_b * half_b - c).max(0.).sqrt();
+
+        Some(SurfaceSample {
+            p: origin + t * dir,
+            pdf: 1. / (2. * PI * (1. - cos_theta_max)),
+        })
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        let mut rec: HitRecord = HitRecord::new();
+        match self.cos_theta_max(origin) {
+            Some(cos_theta_max) if self.hit(&Ray::new(origin, dir), &Interval::new(0.001, f64::INFINITY), &mut rec) => {
+                1. / (2. * PI * (1. - cos_theta_max))
+            }
+            _ => 0.,
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,240 @@
+use std::sync::Arc;
+
+use fastrand::f64;
+
+use crate::materials::Material;
+use crate::utils::Interval;
+use crate::ray::Ray;
+use crate::vec3::{Vec3, Point3};
+
+use super::{Aabb, HitRecord, Object, SurfaceSample};
+
+
+pub struct Triangle {
+    p: [Point3; 3],
+    normals: Option<[Vec3; 3]>,
+    uvs: [(f64, f64); 3],
+    mat: Arc<dyn Material>,
+}
+
+impl Triangle {
+    /// Vertices are expected counter-clockwise when looking at the front face.
+    pub fn new(p0: Point3, p1: Point3, p2: Point3, mat: Arc<dyn Material>) -> Self {
+        Self {
+            p: [p0, p1, p2],
+            normals: None,
+            uvs: [(0., 0.), (1., 0.), (0., 1.)],
+            mat,
+        }
+    }
+
+    /// Sets per-vertex normals, interpolated over the face for shading.
+    pub fn with_normals(mut self, n0: Vec3, n1: Vec3, n2: Vec3) -> Self {
+        self.normals = Some([n0, n1, n2]);
+        self
+    }
+
+    /// Sets per-vertex texture coordinates.
+    pub fn with_uvs(mut self, uv0: (f64, f64), uv1: (f64, f64), uv2: (f64, f64)) -> Self {
+        self.uvs = [uv0, uv1, uv2];
+        self
+    }
+
+    /// Converts the uniform area density of the triangle to a solid angle density, for the point at `to_p` from the origin.
+    fn area_to_solid_angle(&self, to_p: Vec3) -> f64 {
+        let cross: Vec3 = (self.p[1] - self.p[0]) & (self.p[2] - self.p[0]);
+        let area: f64 = cross.len() / 2.;
+        let cosine: f64 = (to_p.unit() * cross.unit()).abs();
+        if area <= 0. || cosine <= 0. { return 0. }
+        to_p.len_squared() / (cosine * area)
+    }
+}
+
+impl Object for Triangle {
+    fn hit(&self, r: &Ray, ray_t: &Interval, rec: &mut HitRecord) -> bool {
+        let Some((t, bary)) = intersect(r, self.p, ray_t) else { return false };
+
+        rec.t = t;
+        rec.p = r.at(t);
+        rec.bary = bary;
+        (rec.u, rec.v) = interpolate_uv(self.uvs, bary);
+
+        let n_outward: Vec3 = ((self.p[1] - self.p[0]) & (self.p[2] - self.p[0])).unit();
+        match self.normals {
+            Some(n) => rec.set_shading_normal(r, n_outward, interpolate_normal(n, bary, n_outward)),
+            None => rec.set_normal(r, n_outward),
+        }
+        rec.set_differentials(r, uv_scale(self.p, self.uvs));
+        if let Some(n) = self.normals {
+            rec.set_normal_differentials(|q| interpolate_normal(n, barycentric(self.p, q), n_outward));
+        }
+        rec.vertex_color = None;
+        rec.mat = self.mat.clone();
+
+        true
+    }
+
+    fn bounding_box(&self) -> Aabb {
+        Aabb::surround(&Aabb::from_points(self.p[0], self.p[1]), &Aabb::from_points(self.p[2], self.p[2]))
+    }
+
+    fn materials(&self) -> Vec<Arc<dyn Material>> {
+        vec![self.mat.clone()]
+    }
+
+    fn is_emissive(&self) -> bool {
+        self.mat.is_emissive()
+    }
+
+    /// Samples the area of the triangle uniformly.
+    fn sample_surface(&self, origin: Point3) -> Option<SurfaceSample> {
+        let p: Point3 = sample_point(self.p);
+        let pdf: f64 = self.area_to_solid_angle(p - origin);
+        if pdf <= 0. { return None }
+        Some(SurfaceSample { p, pdf })
+    }
+
+    fn pdf_value(&self, origin: Point3, dir: Vec3) -> f64 {
+        match intersect(&Ray::new(origin, dir), self.p, &Interval::new(0.001, f64::INFINITY)) {
+            Some((t, _)) => self.area_to_solid_angle(t * dir),
+            None => 0.,
+        }
+    }
+}
+
+/// Picks a point of the triangle `p` uniformly.
+pub(crate) fn sample_point(p: [Point3; 3]) -> Point3 {
+    let r1: f64 = f64().sqrt();
+    let r2: f64 = f64();
+    (1. - r1) * p[0] + r1 * (1. - r2) * p[1] + r1 * r2 * p[2]
+}
+
+/// Interpolates per-vertex texture coordinates with the barycentric coordinates `bary`.
+pub(crate) fn interpolate_uv(uvs: [(f64, f64); 3], bary: Vec3) -> (f64, f64) {
+    (
+        bary.x() * uvs[0].0 + bary.y() * uvs[1].0 + bary.z() * uvs[2].0,
+        bary.x() * uvs[0].1 + bary.y() * uvs[1].1 + bary.z() * uvs[2].1,
+    )
+}
+
+/// Interpolates per-vertex normals with the barycentric coordinates `bary`, or returns `fallback`
+/// where they cancel out, as opposing or zero normals do.
+pub(crate) fn interpolate_normal(normals: [Vec3; 3], bary: Vec3, fallback: Vec3) -> Vec3 {
+    let n: Vec3 = bary.x() * normals[0] + bary.y() * normals[1] + bary.z() * normals[2];
+    if n.near_zero() { fallback } else { n.unit() }
+}
+
+/// Barycentric coordinates of `q`, a point of the plane of the triangle `p`.
+pub(crate) fn barycentric(p: [Point3; 3], q: Point3) -> Vec3 {
+    let n: Vec3 = (p[1] - p[0]) & (p[2] - p[0]);
+    let area: f64 = n.len_squared();
+    if area <= 0. { return Vec3::new(1., 0., 0.) }
+    let b1: f64 = ((q - p[0]) & (p[2] - p[0])) * n / area;
+    let b2: f64 = ((p[1] - p[0]) & (q - p[0])) * n / area;
+    Vec3::new(1. - b1 - b2, b1, b2)
+}
+
+/// Change of texture coordinates per unit of length over the triangle `p`, from the ratio of its areas.
+pub(crate) fn uv_scale(p: [Point3; 3], uvs: [(f64, f64); 3]) -> f64 {
+    let (du1, dv1) = (uvs[1].0 - uvs[0].0, uvs[1].1 - uvs[0].1);
+    let (du2, dv2) = (uvs[2].0 - uvs[0].0, uvs[2].1 - uvs[0].1);
+    let area: f64 = ((p[1] - p[0]) & (p[2] - p[0])).len();
+    if area <= 0. { return 0. }
+    ((du1 * dv2 - du2 * dv1).abs() / area).sqrt()
+}
+
+/// Watertight ray/triangle test (Woop, Benthin & Wald, 2013): rays going through a shared edge or vertex
+/// never slip between the triangles sharing it.
+/// Returns the t value of the hit and the barycentric coordinates of the hit point.
+pub(crate) fn intersect(r: &Ray, p: [Point3; 3], ray_t: &Interval) -> Option<(f64, Vec3)> {
+    let dir: Vec3 = r.dir();
+
+    // Permute the axes so that the ray mostly goes along Z, keeping the winding.
+    let kz: usize = {
+        let (x, y, z) = (dir.x().abs(), dir.y().abs(), dir.z().abs());
+        if x > y && x > z { 0 } else if y > z { 1 } else { 2 }
+    };
+    let (mut kx, mut ky) = ((kz + 1) % 3, (kz + 2) % 3);
+    if dir.axis(kz) < 0. {
+        (kx, ky) = (ky, kx);
+    }
+
+    // Shear and scale the vertices into the ray's space, where the ray is the +Z axis.
+    let sx: f64 = dir.axis(kx) / dir.axis(kz);
+    let sy: f64 = dir.axis(ky) / dir.axis(kz);
+    let sz: f64 = 1. / dir.axis(kz);
+
+    let a: Vec3 = p[0] - r.orig();
+    let b: Vec3 = p[1] - r.orig();
+    let c: Vec3 = p[2] - r.orig();
+    let (ax, ay) = (a.axis(kx) - sx * a.axis(kz), a.axis(ky) - sy * a.axis(kz));
+    let (bx, by) = (b.axis(kx) - sx * b.axis(kz), b.axis(ky) - sy * b.axis(kz));
+    let (cx, cy) = (c.axis(kx) - sx * c.axis(kz), c.axis(ky) - sy * c.axis(kz));
+
+    // Scaled barycentric coordinates, as 2D edge functions.
+    let u: f64 = cx * by - cy * bx;
+    let v: f64 = ax * cy - ay * cx;
+    let w: f64 = bx * ay - by * ax;
+
+    if (u < 0. || v < 0. || w < 0.) && (u > 0. || v > 0. || w > 0.) {
+        return None
+    }
+    let det: f64 = u + v + w;
+    if det == 0. {
+        return None
+    }
+
+    let t_scaled: f64 = u * sz * a.axis(kz) + v * sz * b.axis(kz) + w * sz * c.axis(kz);
+    let t: f64 = t_scaled / det;
+    if !ray_t.surrounds(t) {
+        return None
+    }
+
+    Some((t, Vec3::new(u / det, v / det, w / det)))
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn hit_barycentric() {
+    let p = [Point3::new(0., 0., 0.), Point3::new(1., 0., 0.), Point3::new(0., 1., 0.)];
+    let r = Ray::new(Point3::new(0.25, 0.5, 1.), Vec3::new(0., 0., -2.));
+
+    let (t, bary) = intersect(&r, p, &Interval::new(0.001, f64::INFINITY)).unwrap();
+
+    assert!(t == 0.5);
+    assert!((bary - Vec3::new(0.25, 0.25, 0.5)).len() < 1e-12);
+    assert!((barycentric(p, r.at(t)) - bary).len() < 1e-12);
+}
+#[test]
+fn zero_normals_fall_back_to_the_face() {
+    use crate::materials::Lambertian;
+    use crate::vec3::Color3;
+
+    // Normals a broken file could hold, which have no direction to interpolate.
+    let up: Vec3 = Vec3::new(0., 0., 1.);
+    let tri = Triangle::new(Point3::new(0., 0., 0.), Point3::new(1., 0., 0.), Point3::new(0., 1., 0.), Arc::new(Lambertian::new(Color3::new(1., 1., 1.))))
+        .with_normals(Vec3::default(), Vec3::default(), Vec3::default());
+    let mut rec: HitRecord = HitRecord::new();
+    let r = Ray::new(Point3::new(0.25, 0.25, 1.), Vec3::new(0., 0., -1.));
+    assert!(tri.hit(&r, &Interval::new(0.001, f64::INFINITY), &mut rec));
+    assert_eq!(rec.normal, up);
+}
+#[test]
+fn watertight_shared_edge() {
+    // Two triangles splitting a quad along its diagonal.
+    let first = [Point3::new(0., 0., 0.), Point3::new(1., 0., 0.), Point3::new(1., 1., 0.)];
+    let second = [Point3::new(0., 0., 0.), Point3::new(1., 1., 0.), Point3::new(0., 1., 0.)];
+    let ray_t = Interval::new(0.001, f64::INFINITY);
+
+    fastrand::seed(3);
+    for _ in 0..10000 {
+        let on_edge = fastrand::f64();
+        let orig = Point3::new(fastrand::f64() * 4. - 2., fastrand::f64() * 4. - 2., 1. + fastrand::f64());
+        let r = Ray::new(orig, Point3::new(on_edge, on_edge, 0.) - orig);
+
+        assert!(intersect(&r, first, &ray_t).is_some() || intersect(&r, second, &ray_t).is_some());
+    }
+}
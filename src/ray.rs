@@ -1,8 +1,20 @@
 use crate::vec3::{Point3, Vec3};
 
+#[derive(Debug, Clone, Copy)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    differentials: Option<Differentials>,
+}
+
+/// The rays through the next pixel to the right and the next one down, whose hits tell
+/// how much of the surface a pixel covers, so that textures can be filtered over it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Differentials {
+    pub rx_origin: Point3,
+    pub rx_dir: Vec3,
+    pub ry_origin: Point3,
+    pub ry_dir: Vec3,
 }
 
 impl Ray {
@@ -10,9 +22,15 @@ impl Ray {
         Self {
             orig: origin,
             dir: direction,
+            differentials: None,
         }
     }
 
+    pub fn with_differentials(mut self, differentials: Differentials) -> Self {
+        self.differentials = Some(differentials);
+        self
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + self.dir * t
     } 
@@ -23,4 +41,8 @@ impl Ray {
     pub fn dir(&self) -> Vec3 {
         self.dir
     }
-}
\ No newline at end of file
+    /// Only camera rays and the rays they are specularly reflected or refracted into have some.
+    pub fn differentials(&self) -> Option<&Differentials> {
+        self.differentials.as_ref()
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::camera::CameraConfig;
+use crate::image::{Denoiser, ExrOptions, ToneMapping};
+use crate::integrators::IntegratorKind;
+use crate::materials::Material;
+use crate::objects::{FlatBvh, Lights, Object, Objects};
+use crate::ray::Ray;
+use crate::vec3::{Color3, Vec3};
+
+/// Everything rays can meet: the objects, sorted into a BVH, the list of lights among them,
+/// and the background seen by rays escaping.
+pub struct Scene {
+    pub world: FlatBvh,
+    pub lights: Lights,
+    pub background: Background,
+    /// IDs of the materials, keyed by address, numbered from 1 in the order they are first met.
+    material_ids: HashMap<usize, u32>,
+}
+
+impl Scene {
+    pub fn new(objects: Objects) -> Self {
+        let mut material_ids: HashMap<usize, u32> = HashMap::new();
+        for mat in objects.materials() {
+            let next: u32 = material_ids.len() as u32 + 1;
+            material_ids.entry(address(&mat)).or_insert(next);
+        }
+
+        Self {
+            lights: Lights::new(&objects),
+            world: FlatBvh::new(objects),
+            background: Background::Sky,
+            material_ids,
+        }
+    }
+
+    /// The ID of `mat`, or 0 if it isn't used by the scene.
+    pub fn material_id(&self, mat: &Arc<dyn Material>) -> u32 {
+        self.material_ids.get(&address(mat)).copied().unwrap_or(0)
+    }
+}
+
+/// Identifies a shared material by the address of its data.
+fn address(mat: &Arc<dyn Material>) -> usize {
+    Arc::as_ptr(mat) as *const () as usize
+}
+
+/// How a scene is rendered and saved, apart from the camera.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderSettings {
+    pub integrator: IntegratorKind,
+    pub max_depth: u32,
+    /// Image file written, in the format given by its extension.
+    pub output: PathBuf,
+    pub tone_mapping: ToneMapping,
+    /// Pixel type and compression of OpenEXR outputs.
+    pub exr: ExrOptions,
+    /// Filter run on the image when set, which also renders the output variables it needs.
+    pub denoiser: Option<Denoiser>,
+    /// Also writes the output variables, as layers of OpenEXR images or as images next to the output.
+    /// See [`crate::image::save_layers`].
+    pub aovs: bool,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            integrator: IntegratorKind::Path,
+            max_depth: 10,
+            output: PathBuf::from("out.png"),
+            tone_mapping: ToneMapping::default(),
+            exr: ExrOptions::default(),
+            denoiser: None,
+            aovs: false,
+        }
+    }
+}
+
+/// Everything a scene file describes: the scene, the camera looking at it and how to render it.
+pub struct SceneDescription {
+    pub scene: Scene,
+    pub camera: CameraConfig,
+    pub settings: RenderSettings,
+}
+
+/// What rays see when they leave the scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    /// A vertical white to pink gradient, also lighting the scene.
+    Sky,
+    /// A uniform color. Black makes the emissive materials the only lights.
+    Solid(Color3),
+}
+
+impl Background {
+    pub fn color(&self, r: &Ray) -> Color3 {
+        match self {
+            Background::Sky => {
+                let unit: Vec3 = r.dir().unit();
+                let alpha: f64 = 0.5 * (unit.y() + 1.);
+                (1.-alpha) * Color3::new(1., 1., 1.) + alpha * Color3::new(1.0, 0.5, 0.8)
+            }
+            Background::Solid(color) => *color,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use crate::vec3::{Color3, Point3};
+
+use super::{SolidColor, Texture};
+
+/// Cubes of side `scale` alternating between two textures, in world space.
+pub struct Checker {
+    inv_scale: f64,
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+}
+
+impl Checker {
+    pub fn new(scale: f64, even: Arc<dyn Texture>, odd: Arc<dyn Texture>) -> Self {
+        Self {
+            inv_scale: 1. / scale,
+            even,
+            odd,
+        }
+    }
+
+    pub fn from_colors(scale: f64, even: Color3, odd: Color3) -> Self {
+        Self::new(scale, Arc::new(SolidColor::new(even)), Arc::new(SolidColor::new(odd)))
+    }
+}
+
+impl Checker {
+    fn pick(&self, p: Point3) -> &dyn Texture {
+        let cell = |c: f64| (self.inv_scale * c).floor() as i64;
+        if (cell(p.x()) + cell(p.y()) + cell(p.z())) % 2 == 0 { self.even.as_ref() } else { self.odd.as_ref() }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color3 {
+        self.pick(p).value(u, v, p)
+    }
+
+    fn value_lod(&self, u: f64, v: f64, p: Point3, width: f64) -> Color3 {
+        self.pick(p).value_lod(u, v, p, width)
+    }
+}
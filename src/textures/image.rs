@@ -0,0 +1,147 @@
+use std::path::Path;
+
+use crate::image::{self, Framebuffer};
+use crate::loaders::LoadError;
+use crate::vec3::{Color3, Point3};
+
+use super::Texture;
+
+/// How texture coordinates outside of [0; 1] are brought back into the image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Wrap {
+    /// Tiles the image.
+    Repeat,
+    /// Stretches the pixels of the border.
+    Clamp,
+    /// Tiles the image, flipped every other time.
+    Mirror,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    /// Bilinear in the two levels of the mip pyramid closest to the footprint of the ray, blended.
+    Trilinear,
+}
+
+/// An image laid over the surface coordinates, (0, 0) being its bottom left corner.
+/// Repeated and filtered trilinearly by default.
+pub struct ImageTexture {
+    /// The image, then versions of half the size down to a single pixel.
+    levels: Vec<Framebuffer>,
+    wrap: Wrap,
+    filter: Filter,
+}
+
+impl ImageTexture {
+    /// `image` holds linear colors.
+    pub fn new(image: Framebuffer) -> Self {
+        let mut levels: Vec<Framebuffer> = vec![image];
+        while let Some(level) = levels.last().filter(|l| l.width() > 1 || l.height() > 1) {
+            levels.push(downsample(level));
+        }
+        Self {
+            levels,
+            wrap: Wrap::Repeat,
+            filter: Filter::Trilinear,
+        }
+    }
+
+    /// Reads the image file at `path`, see [`image::load`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        Ok(Self::new(image::load(path)?))
+    }
+
+    pub fn with_wrap(mut self, wrap: Wrap) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Number of levels of the mip pyramid.
+    pub fn levels(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// The pixel of `level` at (`x`, `y`), wrapped.
+    fn texel(&self, level: &Framebuffer, x: i64, y: i64) -> Color3 {
+        level.get(self.wrap.apply(x, level.width()), self.wrap.apply(y, level.height()))
+    }
+
+    fn nearest(&self, level: &Framebuffer, u: f64, v: f64) -> Color3 {
+        let (x, y) = (u * level.width() as f64, (1. - v) * level.height() as f64);
+        self.texel(level, x.floor() as i64, y.floor() as i64)
+    }
+
+    fn bilinear(&self, level: &Framebuffer, u: f64, v: f64) -> Color3 {
+        // Pixel centers are at half coordinates.
+        let (x, y) = (u * level.width() as f64 - 0.5, (1. - v) * level.height() as f64 - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        (1. - fy) * ((1. - fx) * self.texel(level, x0, y0) + fx * self.texel(level, x0 + 1, y0))
+            + fy * ((1. - fx) * self.texel(level, x0, y0 + 1) + fx * self.texel(level, x0 + 1, y0 + 1))
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color3 {
+        self.value_lod(u, v, p, 0.)
+    }
+
+    fn value_lod(&self, u: f64, v: f64, _p: Point3, width: f64) -> Color3 {
+        let image: &Framebuffer = &self.levels[0];
+        if image.width() == 0 || image.height() == 0 {
+            return Color3::new(0., 1., 1.);
+        }
+        match self.filter {
+            Filter::Nearest => self.nearest(image, u, v),
+            Filter::Bilinear => self.bilinear(image, u, v),
+            Filter::Trilinear => {
+                // The level where the footprint is a pixel wide.
+                let texels: f64 = width * image.width().max(image.height()) as f64;
+                let lod: f64 = if texels > 1. { texels.log2().min((self.levels.len() - 1) as f64) } else { 0. };
+                let (fine, t) = (lod.floor() as usize, lod.fract());
+                let color: Color3 = self.bilinear(&self.levels[fine], u, v);
+                if t > 0. {
+                    (1. - t) * color + t * self.bilinear(&self.levels[fine + 1], u, v)
+                } else {
+                    color
+                }
+            }
+        }
+    }
+}
+
+impl Wrap {
+    /// Brings the pixel coordinate `i` into [0; `size`).
+    fn apply(self, i: i64, size: u32) -> u32 {
+        let size: i64 = size as i64;
+        (match self {
+            Wrap::Repeat => i.rem_euclid(size),
+            Wrap::Clamp => i.clamp(0, size - 1),
+            Wrap::Mirror => {
+                let i: i64 = i.rem_euclid(2 * size);
+                if i < size { i } else { 2 * size - 1 - i }
+            }
+        }) as u32
+    }
+}
+
+/// Halves the size of `image`, averaging blocks of 2x2 pixels. Odd sizes reuse the last row or column.
+fn downsample(image: &Framebuffer) -> Framebuffer {
+    let (width, height) = (image.width().div_ceil(2), image.height().div_ceil(2));
+    let mut out = Framebuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let texel = |dx: u32, dy: u32| image.get((2 * x + dx).min(image.width() - 1), (2 * y + dy).min(image.height() - 1));
+            out.set(x, y, (texel(0, 0) + texel(1, 0) + texel(0, 1) + texel(1, 1)) / 4.);
+        }
+    }
+    out
+}
@@ -0,0 +1,87 @@
+use crate::vec3::{Color3, Point3};
+
+mod solid;
+pub use solid::*;
+mod checker;
+pub use checker::*;
+mod image;
+pub use image::*;
+mod noise;
+pub use noise::*;
+
+/// A color varying over surfaces.
+pub trait Texture: Send + Sync {
+    /// Returns the color at the surface coordinates (`u`, `v`) of the hit point `p`.
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color3;
+    /// Same as [`Texture::value`], averaged over a footprint `width` wide in texture coordinates.
+    /// Textures that can't filter themselves are point sampled.
+    fn value_lod(&self, u: f64, v: f64, p: Point3, _width: f64) -> Color3 {
+        self.value(u, v, p)
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn texture_values() {
+    use std::sync::Arc;
+
+    use crate::image::Framebuffer;
+    use crate::materials::Lambertian;
+    use crate::objects::{HitRecord, Object, Sphere};
+    use crate::ray::Ray;
+    use crate::utils::Interval;
+    use crate::vec3::Vec3;
+
+    let (black, white) = (Color3::new(0., 0., 0.), Color3::new(1., 1., 1.));
+    let checker = Checker::from_colors(0.5, black, white);
+    assert_eq!(checker.value(0., 0., Point3::new(0.1, 0.1, 0.1)), black);
+    assert_eq!(checker.value(0., 0., Point3::new(0.6, 0.1, 0.1)), white);
+    assert_eq!(checker.value(0., 0., Point3::new(-0.1, 0.1, 0.1)), white);
+
+    // (0, 0) is the bottom left corner of the image.
+    let mut fb = Framebuffer::new(2, 2);
+    fb.set(0, 1, Color3::new(1., 0., 0.));
+    fb.set(1, 0, Color3::new(0., 0., 1.));
+    let image = ImageTexture::new(fb.clone()).with_filter(Filter::Nearest);
+    assert_eq!(image.value(0.1, 0.1, Point3::default()), Color3::new(1., 0., 0.));
+    assert_eq!(image.value(0.9, 0.9, Point3::default()), Color3::new(0., 0., 1.));
+    assert_eq!(image.value(-0.9, 3.1, Point3::default()), Color3::new(1., 0., 0.));
+    assert_eq!(image.with_wrap(Wrap::Mirror).value(-0.1, 0.1, Point3::default()), Color3::new(1., 0., 0.));
+
+    // Halfway between pixel centers, then clamped to the border
+    let image = ImageTexture::new(fb).with_wrap(Wrap::Clamp).with_filter(Filter::Bilinear);
+    assert_eq!(image.value(0.5, 0.75, Point3::default()), Color3::new(0., 0., 0.5));
+    assert_eq!(image.value(0.5, 0.25, Point3::default()), Color3::new(0.5, 0., 0.));
+    assert_eq!(image.value(0.5, 2., Point3::default()), Color3::new(0., 0., 0.5));
+
+    // Stripes one pixel wide average out from afar.
+    let mut stripes = Framebuffer::new(64, 8);
+    for y in 0..8 {
+        for x in (0..64).step_by(2) {
+            stripes.set(x, y, white);
+        }
+    }
+    let image = ImageTexture::new(stripes);
+    assert_eq!(image.levels(), 7);
+    let gray: Color3 = image.value_lod(0.3, 0.5, Point3::default(), 0.5);
+    assert!((gray - Color3::new(0.5, 0.5, 0.5)).len() < 1e-9);
+    let sharp: Color3 = image.value_lod(1. / 128., 0.5, Point3::default(), 1. / 64.);
+    assert!((sharp - white).len() < 1e-9);
+
+    // Spheres: u turns from -X, v goes up.
+    let sphere = Sphere::new(Point3::default(), 2., Arc::new(Lambertian::new(white)));
+    let uv = |dir: Vec3| {
+        let mut rec: HitRecord = HitRecord::new();
+        assert!(sphere.hit(&Ray::new(Point3::default() + 5. * dir, -dir), &Interval::new(0.001, f64::INFINITY), &mut rec));
+        (rec.u, rec.v)
+    };
+    let near = |(u, v): (f64, f64), expected: (f64, f64)| (u - expected.0).abs() < 1e-9 && (v - expected.1).abs() < 1e-9;
+    assert!(near(uv(Vec3::new(-1., 0., 0.)), (0., 0.5)) || near(uv(Vec3::new(-1., 0., 0.)), (1., 0.5)));
+    assert!(near(uv(Vec3::new(0., 0., 1.)), (0.25, 0.5)));
+    assert!(near(uv(Vec3::new(1., 0., 0.)), (0.5, 0.5)));
+    assert!(near(uv(Vec3::new(0., 0., -1.)), (0.75, 0.5)));
+    assert!(near(uv(Vec3::new(0., 1., 0.)), (0.5, 1.)));
+}
@@ -0,0 +1,214 @@
+use fastrand::Rng;
+
+use crate::vec3::{Color3, Point3, Vec3};
+
+use super::Texture;
+
+/// Gradient and cellular noise over space, the same for the same seed.
+#[derive(Debug, Clone)]
+pub struct Noise {
+    /// A shuffle of 0..256, twice, to hash lattice points without wrapping indices.
+    perm: [u8; 512],
+}
+
+impl Noise {
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        Rng::with_seed(seed).shuffle(&mut table);
+        let mut perm: [u8; 512] = [0; 512];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = table[i % 256];
+        }
+        Self {
+            perm,
+        }
+    }
+
+    /// Hashes the lattice point (`x`, `y`, `z`) to a byte.
+    fn hash(&self, x: i64, y: i64, z: i64) -> u8 {
+        let p = |i: usize| self.perm[i] as usize;
+        self.perm[p(p((x & 255) as usize) + (y & 255) as usize) + (z & 255) as usize]
+    }
+
+    /// Improved Perlin noise (Perlin 2002), between -1 and 1 and zero on the lattice points.
+    pub fn perlin(&self, p: Point3) -> f64 {
+        let (fx, fy, fz) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (x, y, z) = (fx as i64, fy as i64, fz as i64);
+        let (dx, dy, dz) = (p.x() - fx, p.y() - fy, p.z() - fz);
+        let fade = |t: f64| t * t * t * (t * (t * 6. - 15.) + 10.);
+        let (u, v, w) = (fade(dx), fade(dy), fade(dz));
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+
+        let corner = |i: i64, j: i64, k: i64| grad(self.hash(x + i, y + j, z + k), dx - i as f64, dy - j as f64, dz - k as f64);
+        lerp(w,
+            lerp(v, lerp(u, corner(0, 0, 0), corner(1, 0, 0)), lerp(u, corner(0, 1, 0), corner(1, 1, 0))),
+            lerp(v, lerp(u, corner(0, 0, 1), corner(1, 0, 1)), lerp(u, corner(0, 1, 1), corner(1, 1, 1))),
+        )
+    }
+
+    /// Fractal Brownian motion: `octaves` layers of noise, each twice finer and half as strong, between -1 and 1.
+    pub fn fbm(&self, p: Point3, octaves: u32) -> f64 {
+        self.octaves(p, octaves, |n| n)
+    }
+
+    /// Same as [`Noise::fbm`] with the absolute value of each layer, between 0 and 1.
+    pub fn turbulence(&self, p: Point3, octaves: u32) -> f64 {
+        self.octaves(p, octaves, f64::abs)
+    }
+
+    fn octaves(&self, p: Point3, octaves: u32, layer: impl Fn(f64) -> f64) -> f64 {
+        let (mut sum, mut total, mut weight, mut q) = (0., 0., 1., p);
+        for _ in 0..octaves.max(1) {
+            sum += weight * layer(self.perlin(q));
+            total += weight;
+            weight *= 0.5;
+            q *= 2.;
+        }
+        sum / total
+    }
+
+    /// Worley noise: the distance to the closest of points scattered one per unit cube, up to 1.
+    pub fn worley(&self, p: Point3) -> f64 {
+        let (x, y, z) = (p.x().floor() as i64, p.y().floor() as i64, p.z().floor() as i64);
+        let mut closest: f64 = 1.;
+        for i in x - 1..=x + 1 {
+            for j in y - 1..=y + 1 {
+                for k in z - 1..=z + 1 {
+                    // Three more hashes of the cell place its point.
+                    let h: u8 = self.hash(i, j, k);
+                    let offset = |n: i64| self.hash(i + n, j, k ^ h as i64) as f64 / 256.;
+                    let point: Point3 = Point3::new(i as f64 + offset(17), j as f64 + offset(31), k as f64 + offset(47));
+                    closest = closest.min((point - p).len());
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// Dot product of (`x`, `y`, `z`) with one of the 12 edge directions of a cube, picked by `hash`.
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h: u8 = hash & 15;
+    let u: f64 = if h < 8 { x } else { y };
+    let v: f64 = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// The looks [`NoiseTexture`] can take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NoisePattern {
+    /// Soft clouds of fractal noise.
+    Fbm,
+    /// Billowy clouds, with sharp creases.
+    Turbulence,
+    /// Veins along the Z axis, distorted by turbulence.
+    Marble,
+    /// Rings around the Y axis, distorted by fractal noise.
+    Wood,
+    /// Cells, dark at their centers.
+    Worley,
+}
+
+/// A procedural pattern blending between two colors in world space.
+#[derive(Debug, Clone)]
+pub struct NoiseTexture {
+    noise: Noise,
+    pattern: NoisePattern,
+    scale: f64,
+    octaves: u32,
+    low: Color3,
+    high: Color3,
+}
+
+impl NoiseTexture {
+    /// Black to white, with features about 1 unit apart and 6 octaves.
+    pub fn new(pattern: NoisePattern, seed: u64) -> Self {
+        Self {
+            noise: Noise::new(seed),
+            pattern,
+            scale: 1.,
+            octaves: 6,
+            low: Color3::new(0., 0., 0.),
+            high: Color3::new(1., 1., 1.),
+        }
+    }
+
+    /// Number of features per unit of length.
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Number of layers of the fractal patterns.
+    pub fn with_octaves(mut self, octaves: u32) -> Self {
+        self.octaves = octaves;
+        self
+    }
+
+    pub fn with_colors(mut self, low: Color3, high: Color3) -> Self {
+        self.low = low;
+        self.high = high;
+        self
+    }
+
+    /// The pattern at `p`, between 0 and 1.
+    pub fn pattern(&self, p: Point3) -> f64 {
+        let q: Point3 = self.scale * p;
+        let t: f64 = match self.pattern {
+            NoisePattern::Fbm => 0.5 * (1. + self.noise.fbm(q, self.octaves)),
+            NoisePattern::Turbulence => self.noise.turbulence(q, self.octaves),
+            NoisePattern::Marble => 0.5 * (1. + (q.z() + 4. * self.noise.turbulence(q, self.octaves)).sin()),
+            NoisePattern::Wood => {
+                let rings: f64 = Vec3::new(q.x(), 0., q.z()).len() + 0.4 * self.noise.fbm(q, self.octaves);
+                rings - rings.floor()
+            }
+            NoisePattern::Worley => self.noise.worley(q),
+        };
+        t.clamp(0., 1.)
+    }
+}
+
+impl Texture for NoiseTexture {
+    fn value(&self, _u: f64, _v: f64, p: Point3) -> Color3 {
+        let t: f64 = self.pattern(p);
+        (1. - t) * self.low + t * self.high
+    }
+}
+
+
+// -------------------------------------------------- Tests
+
+
+#[test]
+fn noise_patterns() {
+    fastrand::seed(9);
+    let noise = Noise::new(1);
+    let points: Vec<Point3> = (0..500).map(|_| Vec3::rand(-20., 20.)).collect();
+
+    // Zero on the lattice, smooth in between.
+    assert_eq!(noise.perlin(Point3::new(3., -2., 7.)), 0.);
+    for &p in &points {
+        let n: f64 = noise.perlin(p);
+        assert!((-1. ..=1.).contains(&n));
+        assert!((noise.perlin(p + Vec3::new(1e-4, 0., 0.)) - n).abs() < 1e-3);
+        assert!((0. ..=1.).contains(&noise.turbulence(p, 5)));
+        assert!((0. ..=1.).contains(&noise.worley(p)));
+    }
+    assert!(points.iter().any(|&p| noise.perlin(p) > 0.2) && points.iter().any(|&p| noise.perlin(p) < -0.2));
+    assert!(points.iter().any(|&p| noise.worley(p) < 0.2) && points.iter().any(|&p| noise.worley(p) > 0.5));
+
+    // The same seed gives the same patterns, another seed others.
+    let other = Noise::new(2);
+    assert!(points.iter().all(|&p| Noise::new(1).fbm(p, 4) == noise.fbm(p, 4)));
+    assert!(points.iter().any(|&p| other.fbm(p, 4) != noise.fbm(p, 4)));
+
+    let (brown, cream) = (Color3::new(0.4, 0.2, 0.1), Color3::new(0.9, 0.8, 0.6));
+    for pattern in [NoisePattern::Fbm, NoisePattern::Turbulence, NoisePattern::Marble, NoisePattern::Wood, NoisePattern::Worley] {
+        let tex = NoiseTexture::new(pattern, 1).with_scale(0.5).with_colors(brown, cream);
+        for &p in &points {
+            let c: Color3 = tex.value(0., 0., p);
+            assert!(c.x() >= brown.x() - 1e-9 && c.x() <= cream.x() + 1e-9);
+            assert_eq!(c, tex.value(0.5, 0.5, p));
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use crate::vec3::{Color3, Point3};
+
+use super::Texture;
+
+/// The same color everywhere.
+pub struct SolidColor {
+    color: Color3,
+}
+
+impl SolidColor {
+    pub fn new(color: Color3) -> Self {
+        Self {
+            color,
+        }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _u: f64, _v: f64, _p: Point3) -> Color3 {
+        self.color
+    }
+}
@@ -10,16 +10,31 @@ pub fn deg_to_rad(degrees: f64) -> f64 {
     degrees * PI / 180.
 }
 
+/// Decodes an sRGB-encoded channel value in [0; 1] to linear.
+pub fn srgb_to_linear(c: f64) -> f64 {
+    if c <= 0.04045 { c / 12.92 }
+    else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Encodes a linear channel value in [0; 1] with the sRGB transfer function.
+pub fn linear_to_srgb(c: f64) -> f64 {
+    if c <= 0.0031308 { c * 12.92 }
+    else { 1.055 * c.powf(1. / 2.4) - 0.055 }
+}
+
 // pub fn rad_to_deg(radians: f64) -> f64 {
 //     radians / PI * 180.
 // }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Interval {
     pub min: f64,
     pub max: f64,
 }
 
 impl Interval {
+    pub const EMPTY: Interval = Interval { min: f64::INFINITY, max: f64::NEG_INFINITY };
+
     pub fn new(min: f64, max: f64) -> Self {
         Self {
             min,
@@ -27,6 +42,23 @@ impl Interval {
         }
     }
 
+    /// The smallest interval containing both `a` and `b`.
+    pub fn union(a: Interval, b: Interval) -> Self {
+        Self {
+            min: a.min.min(b.min),
+            max: a.max.max(b.max),
+        }
+    }
+
+    pub fn size(&self) -> f64 {
+        self.max - self.min
+    }
+
+    /// Returns this interval widened by `delta`, half on each side.
+    pub fn expand(&self, delta: f64) -> Self {
+        Self::new(self.min - delta / 2., self.max + delta / 2.)
+    }
+
     // pub fn contains(&self, x: f64) -> bool {
     //     self.min <= x && x <= self.max
     // }
@@ -1,4 +1,4 @@
-use std::{f64::EPSILON, ops::{Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign}};
+use std::{ops::{Add, AddAssign, BitAnd, BitAndAssign, BitOr, BitOrAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign}};
 
 use fastrand::f64;
 
@@ -7,6 +7,18 @@ use crate::utils::rand_within;
 pub type Point3 = Vec3;
 pub type Color3 = Vec3;
 
+/// A 3D vector, also used for points and linear RGB colors.
+/// Besides the arithmetic operators, `*` between vectors is the dot product, `&` the cross product
+/// and `|` the reflection about a normal.
+///
+/// ```
+/// use rustracer::vec3::Vec3;
+///
+/// let (x, y) = (Vec3::new(1., 0., 0.), Vec3::new(0., 1., 0.));
+/// assert_eq!(x * y, 0.);
+/// assert_eq!(x & y, Vec3::new(0., 0., 1.));
+/// assert_eq!(Vec3::new(1., -1., 0.) | y, Vec3::new(1., 1., 0.));
+/// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vec3 {
     x: f64,
@@ -53,7 +65,7 @@ impl Vec3 {
     }
 
     pub fn near_zero(&self) -> bool {
-        self.x.abs() < EPSILON && self.y.abs() < EPSILON && self.z.abs() < EPSILON
+        self.x.abs() < f64::EPSILON && self.y.abs() < f64::EPSILON && self.z.abs() < f64::EPSILON
     }
 
     pub fn x(&self) -> f64 {
@@ -65,6 +77,14 @@ impl Vec3 {
     pub fn z(&self) -> f64 {
         self.z
     }
+    /// Returns the coordinate along axis `n` (0 = X, 1 = Y, 2 = Z).
+    pub fn axis(&self, n: usize) -> f64 {
+        match n {
+            1 => self.y,
+            2 => self.z,
+            _ => self.x,
+        }
+    }
 
     pub fn negate(&mut self) {
         *self = -*self;
@@ -99,7 +119,7 @@ impl Vec3 {
     }
     pub fn normalize(&mut self) {
         let len = self.len();
-        *self = *self / len;
+        *self /= len;
     }
 
     /// Clamps all values to the interval [min; max]
@@ -109,6 +129,18 @@ impl Vec3 {
         self.z = self.z.clamp(min, max);
     }
 
+    /// Returns two unit vectors forming an orthonormal basis with `self`, which must be a unit vector.
+    pub fn orthonormal_basis(&self) -> (Vec3, Vec3) {
+        // Duff et al., "Building an Orthonormal Basis, Revisited" (2017)
+        let sign: f64 = 1f64.copysign(self.z);
+        let a: f64 = -1. / (sign + self.z);
+        let b: f64 = self.x * self.y * a;
+        (
+            Vec3::new(1. + sign * self.x * self.x * a, sign * b, -sign * self.x),
+            Vec3::new(b, sign + self.y * self.y * a, -self.y),
+        )
+    }
+
     pub fn refract(&self, n: Vec3, etai_over_etat: f64) -> Vec3 {
         let cos_theta = ((-*self) * n).min(1.0);
         let r_out_perp: Vec3 = etai_over_etat * (*self + cos_theta * n);
@@ -118,18 +150,17 @@ impl Vec3 {
 }
 
 impl Color3 {
-    /// The string to be written in order to represent this color in a ppm file
-    /// Expects normalized float color values
-    pub fn ppm(&self) -> String {
-        format!("{} {} {}\n", (self.x * 255.999).floor() as u32, (self.y * 255.999).floor() as u32, (self.z * 255.999).floor() as u32)
-    }
-
     /// Converts this color to from linear to gamma 2.
     pub fn to_gamma_2(&mut self) {
         self.x = self.x.sqrt();
         self.y = self.y.sqrt();
         self.z = self.z.sqrt();
     }
+
+    /// Relative luminance of this linear sRGB color.
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
 }
 
 // ------------------------- General